@@ -0,0 +1,41 @@
+//! Decode cost of one SLAM packet, full sample vs pose only.
+//!
+//! Run with `cargo bench --bench parse`. Plain `std::time` timing, no harness.
+
+use std::hint::black_box;
+use std::time::Instant;
+use xvisio::protocol::{parse_pose_into, parse_sample};
+use xvisio::Pose;
+
+/// Example packet from PROTOCOL.md.
+const PACKET: [u8; 63] = [
+    0x01, 0xa2, 0x33, 0x6b, 0xd1, 0x25, 0x5f, 0x58, 0x01, 0x00, 0x00, 0x1e, 0x00, 0x00, 0x00, 0xc3,
+    0x01, 0x00, 0x00, 0x62, 0xc0, 0x3a, 0x03, 0x2d, 0x06, 0x5a, 0xfd, 0x56, 0xc0, 0xf3, 0x05, 0x72,
+    0x06, 0xa9, 0x05, 0x6c, 0x3f, 0xa0, 0x56, 0x7d, 0x00, 0xf3, 0xff, 0xf2, 0xff, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x04, 0x00, 0x09, 0x00, 0x07, 0x00, 0x2b, 0x41, 0x00, 0x00, 0x00, 0x00,
+];
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    for _ in 0..ITERATIONS / 10 {
+        f();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let ns = start.elapsed().as_nanos() as f64 / ITERATIONS as f64;
+    println!("{:<16} {:>8.1} ns/packet", name, ns);
+}
+
+fn main() {
+    bench("parse_sample", || {
+        black_box(parse_sample(black_box(&PACKET)));
+    });
+
+    let mut pose = Pose::new([0.0; 3], [0.0, 0.0, 0.0, 1.0]);
+    bench("parse_pose_into", || {
+        black_box(parse_pose_into(black_box(&PACKET), &mut pose));
+    });
+}
@@ -7,9 +7,25 @@ fn main() {
         Ok(devices) => {
             println!("Found {} XR50 device(s):", devices.len());
             for (i, dev) in devices.iter().enumerate() {
+                if let Some(err) = &dev.error {
+                    println!(
+                        "  [{}] Bus={}  present but unreadable: {}",
+                        i, dev.bus_id, err
+                    );
+                    continue;
+                }
                 println!(
-                    "  [{}] UUID={}  FW={}  Features={:?}  Bus={} Addr={}",
-                    i, dev.uuid, dev.version, dev.features, dev.bus_id, dev.device_address
+                    "  [{}] UUID={}  Serial={}  FW={}  Features={:?}  Path={} USB={:03}:{:03}  Usage={:04x}:{:04x}",
+                    i,
+                    dev.uuid,
+                    dev.serial_number.as_deref().unwrap_or("-"),
+                    dev.version,
+                    dev.features,
+                    dev.bus_id,
+                    dev.bus_number,
+                    dev.device_address,
+                    dev.usage_page,
+                    dev.usage
                 );
             }
         }
@@ -8,6 +8,34 @@ fn main() {
             println!("UUID:     {}", device.uuid());
             println!("Version:  {}", device.version());
             println!("Features: {:?}", device.features());
+            match device.report_descriptor() {
+                Ok(d) => println!("HID desc: {} bytes {:02x?}", d.len(), d),
+                Err(e) => println!("HID desc: unavailable ({})", e),
+            }
+            #[cfg(feature = "usb")]
+            match device.usb_info() {
+                Ok(usb) if usb.is_slow() => {
+                    println!("USB:      {:?} speed, too slow for 950 Hz SLAM", usb.speed)
+                }
+                Ok(usb) => println!("USB:      {:?} speed", usb.speed),
+                Err(e) => println!("USB:      unavailable ({})", e),
+            }
+            #[cfg(feature = "usb")]
+            match device.uvc_info() {
+                Ok(Some(uvc)) => {
+                    for s in &uvc.streaming {
+                        println!(
+                            "UVC:      interface {} endpoint {:?}",
+                            s.interface, s.endpoint
+                        );
+                        for f in &s.frames {
+                            println!("          {}x{} @ {:.0} Hz", f.width, f.height, f.fps);
+                        }
+                    }
+                }
+                Ok(None) => println!("UVC:      none"),
+                Err(e) => println!("UVC:      unavailable ({})", e),
+            }
         }
         Err(e) => {
             eprintln!("Error: {}", e);
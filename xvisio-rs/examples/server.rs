@@ -19,15 +19,17 @@ use tungstenite::Message;
 
 const PORT: u16 = 8080;
 
+/// WebSocket clients shared across threads.
+type WsClient = Arc<Mutex<tungstenite::WebSocket<TcpStream>>>;
+type WsClients = Arc<Mutex<Vec<WsClient>>>;
+
 fn main() {
     env_logger::init();
 
     let dist_dir = find_dist_dir();
     eprintln!("[HTTP] Serving static files from: {}", dist_dir.display());
 
-    // WebSocket clients shared across threads
-    type WsClient = Arc<Mutex<tungstenite::WebSocket<TcpStream>>>;
-    let clients: Arc<Mutex<Vec<WsClient>>> = Arc::new(Mutex::new(Vec::new()));
+    let clients: WsClients = Arc::new(Mutex::new(Vec::new()));
 
     // Start XR50 SLAM thread
     let slam_clients = clients.clone();
@@ -77,11 +79,7 @@ fn main() {
 }
 
 /// Route incoming connection to WebSocket or HTTP handler.
-fn handle_connection(
-    stream: TcpStream,
-    clients: Arc<Mutex<Vec<Arc<Mutex<tungstenite::WebSocket<TcpStream>>>>>>,
-    dist_dir: &Path,
-) {
+fn handle_connection(stream: TcpStream, clients: WsClients, dist_dir: &Path) {
     // Set initial timeouts for HTTP; WebSocket handler overrides these
     stream.set_read_timeout(Some(Duration::from_secs(5))).ok();
     stream.set_write_timeout(Some(Duration::from_secs(30))).ok();
@@ -107,10 +105,7 @@ fn handle_connection(
 /// The SLAM thread is the sole writer to the WebSocket (no mutex contention).
 /// This thread just stays alive and detects when the client is removed from
 /// the broadcast list (due to send failure in the SLAM thread).
-fn handle_websocket(
-    stream: TcpStream,
-    clients: Arc<Mutex<Vec<Arc<Mutex<tungstenite::WebSocket<TcpStream>>>>>>,
-) {
+fn handle_websocket(stream: TcpStream, clients: WsClients) {
     // Write timeout prevents the SLAM thread from blocking on a slow client
     stream.set_write_timeout(Some(Duration::from_secs(2))).ok();
 
@@ -220,10 +215,7 @@ fn handle_http(mut stream: TcpStream, request_str: &str, dist_dir: &Path) {
 }
 
 /// SLAM streaming loop — reads XR50 poses and broadcasts JSON to WebSocket clients.
-fn slam_loop(
-    clients: Arc<Mutex<Vec<Arc<Mutex<tungstenite::WebSocket<TcpStream>>>>>>,
-    running: Arc<AtomicBool>,
-) {
+fn slam_loop(clients: WsClients, running: Arc<AtomicBool>) {
     eprintln!("[XR50] Opening device...");
 
     let mut device = match xvisio::Device::open_first() {
@@ -256,8 +248,8 @@ fn slam_loop(
     let mut count: u64 = 0;
     let mut ws_sent: u64 = 0;
     let mut last_report = std::time::Instant::now();
-    let mut last_broadcast = std::time::Instant::now();
-    let broadcast_interval = Duration::from_millis(16); // ~60 Hz to browser
+    // ~60 Hz to the browser, evenly spaced on the device clock
+    let mut display_rate = xvisio::FixedRate::new(60.0);
 
     while running.load(Ordering::Relaxed) {
         let sample = match stream.recv_timeout(Duration::from_secs(2)) {
@@ -273,9 +265,7 @@ fn slam_loop(
         let now = std::time::Instant::now();
 
         // Throttle WebSocket broadcast to ~60 Hz (browser can't use more)
-        if now.duration_since(last_broadcast) >= broadcast_interval {
-            last_broadcast = now;
-
+        if let Some(sample) = display_rate.push(sample) {
             let p = &sample.pose;
             let json = format!(
                 "{{\"x\":{:.4},\"y\":{:.4},\"z\":{:.4},\"roll\":{:.1},\"pitch\":{:.1},\"yaw\":{:.1},\"t\":{}}}",
@@ -1,6 +1,8 @@
 //! Stream 6DOF SLAM pose data from the XR50 to stdout.
 //!
 //! Usage: cargo run --example stream
+//! Set `XVISIO_RAW_CAPTURE=<file>` to also record raw packets (see `xvisio::replay_raw`).
+//! Set `XVISIO_MAX_SECONDS=<n>` to stop after n seconds.
 //! Press Ctrl+C to stop.
 
 use std::time::{Duration, Instant};
@@ -30,7 +32,18 @@ fn main() {
     println!("Features: {:?}", device.features());
     println!();
 
-    let stream = match device.start_slam(mode) {
+    let mut config = xvisio::SlamConfig::new(mode).progress(|phase| eprintln!("  {:?}", phase));
+    if let Ok(path) = std::env::var("XVISIO_RAW_CAPTURE") {
+        println!("Capturing raw packets to {}", path);
+        config = config.raw_capture(path);
+    }
+    if let Some(secs) = std::env::var("XVISIO_MAX_SECONDS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+    {
+        config = config.max_duration(Duration::from_secs(secs));
+    }
+    let stream = match device.start_slam_with_config(&config) {
         Ok(s) => s,
         Err(e) => {
             eprintln!("Failed to start SLAM: {}", e);
@@ -55,11 +68,11 @@ fn main() {
                 // Print every ~100th sample to avoid flooding the terminal
                 if count % 100 == 1 {
                     println!(
-                        "ts={:<12}  pos=[{:+.4}, {:+.4}, {:+.4}]  quat=[{:+.3}, {:+.3}, {:+.3}, {:+.3}]  conf={:.3}",
+                        "ts={:<12}  pos=[{:+.4}, {:+.4}, {:+.4}]  quat=[{:+.3}, {:+.3}, {:+.3}, {:+.3}]  conf_raw={}",
                         p.timestamp_us,
                         p.translation[0], p.translation[1], p.translation[2],
                         p.quaternion[0], p.quaternion[1], p.quaternion[2], p.quaternion[3],
-                        p.confidence,
+                        p.confidence_raw,
                     );
                 }
 
@@ -78,11 +91,19 @@ fn main() {
             Err(xvisio::XvisioError::Timeout) => {
                 idle_timeouts += 1;
                 eprintln!("No SLAM packet for 2s (timeout #{})", idle_timeouts);
+                let unparsed = std::iter::from_fn(|| stream.try_recv_unparsed()).count();
+                if unparsed > 0 {
+                    eprintln!(
+                        "  {} non-edge report(s) received (e.g. Mixed-mode output)",
+                        unparsed
+                    );
+                }
                 if idle_timeouts >= 15 {
                     eprintln!("Stopping after 30s without SLAM packets");
                     break;
                 }
             }
+            Err(xvisio::XvisioError::StreamStopped) => break,
             Err(e) => {
                 eprintln!("Error: {}", e);
                 break;
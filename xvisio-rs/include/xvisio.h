@@ -8,6 +8,67 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * No error has occurred yet. This and the `XV_ERR_*` codes of
+ * `xv_last_error_code` keep their values across releases.
+ */
+#define XV_OK 0
+
+/**
+ * An error without a more specific code; see `xv_last_error` for details.
+ */
+#define XV_ERR_OTHER 1
+
+/**
+ * No XR50 is connected.
+ */
+#define XV_ERR_DEVICE_NOT_FOUND 2
+
+/**
+ * The device dropped off the bus, e.g. re-enumerated; re-opening may succeed.
+ */
+#define XV_ERR_DISCONNECTED 3
+
+/**
+ * No data arrived in time.
+ */
+#define XV_ERR_TIMEOUT 4
+
+/**
+ * The OS denied access to the device (udev rule or sudo missing).
+ */
+#define XV_ERR_PERMISSION 5
+
+/**
+ * Another process or driver holds the device.
+ */
+#define XV_ERR_BUSY 6
+
+/**
+ * The SLAM stream has stopped.
+ */
+#define XV_ERR_STREAM_STOPPED 7
+
+/**
+ * The SLAM stream was stopped because the device timestamp stopped advancing.
+ */
+#define XV_ERR_STREAM_STALLED 8
+
+/**
+ * The device doesn't identify as an XR50.
+ */
+#define XV_ERR_UNSUPPORTED_DEVICE 9
+
+/**
+ * The device answered a command with a malformed or rejecting response.
+ */
+#define XV_ERR_PROTOCOL 10
+
+/**
+ * Standard gravity in m/s², the magnitude [`ImuCalibration::at_rest`] scales to.
+ */
+#define STANDARD_GRAVITY 9.80665
+
 #define VID 1038
 
 #define PID 62472
@@ -16,8 +77,23 @@
 
 #define SLAM_ENDPOINT 131
 
+/**
+ * Vendor-defined HID usage pages span 0xFF00..=0xFFFF; the command collection lives there.
+ */
+#define HID_USAGE_PAGE_VENDOR 65280
+
 #define REPORT_SIZE 63
 
+/**
+ * Nominal rate of the edge SLAM stream; measured rates range ~880-980 Hz.
+ */
+#define NOMINAL_RATE_HZ 950.0
+
+/**
+ * Bytes covering header, timestamp, translation and rotation; the rest is extended data.
+ */
+#define POSE_SIZE 37
+
 /**
  * Fixed-point scale factor: 2^(-14) = 1/16384.
  */
@@ -27,6 +103,11 @@
 
 #define PREFIX_DEVICE_TO_HOST 1
 
+/**
+ * Identity poses (~1 s at 950 Hz) after which the cameras are reported inactive.
+ */
+#define CAMERAS_INACTIVE_SAMPLES 1000
+
 /**
  * Opaque device handle for C consumers.
  */
@@ -61,6 +142,19 @@ typedef struct XvDeviceInfo {
      * USB device address.
      */
     uint8_t address;
+    /**
+     * USB bus number. With `address`, identifies the device when `bus_id` is truncated.
+     */
+    uint8_t bus_number;
+    /**
+     * HID usage page of the command collection, 0 if unknown. Selects the same
+     * collection again in `xv_open_device`.
+     */
+    uint16_t usage_page;
+    /**
+     * HID usage of the command collection.
+     */
+    uint16_t usage;
 } XvDeviceInfo;
 
 /**
@@ -88,13 +182,21 @@ typedef struct XvPose {
      */
     double host_timestamp_s;
     /**
-     * Tracking confidence [0..1].
+     * Clamped, scaled `confidence_raw`; a tracking hint, not a graded quality.
      */
     double confidence;
     /**
      * Euler angles [roll, pitch, yaw] in degrees.
      */
     double euler_deg[3];
+    /**
+     * Unscaled packet word that `confidence` is derived from.
+     */
+    int16_t confidence_raw;
+    /**
+     * Decoded rotation payload: 0 = matrix, 1 = quaternion.
+     */
+    uint8_t rotation_source;
 } XvPose;
 
 #ifdef __cplusplus
@@ -104,14 +206,21 @@ extern "C" {
 /**
  * List connected XR50 devices.
  *
- * Writes up to `max` entries into `out`. Returns the number of devices found,
- * or -1 on error.
+ * Writes up to `max` entries into `out` and returns the number written, or -1
+ * on error. With `out` null or `max` <= 0 nothing is written and the total
+ * number of devices is returned, so callers can size the array first.
  *
  * # Safety
  * `out` must point to an array of at least `max` `XvDeviceInfo` elements, or be null.
  */
 int xv_list_devices(struct XvDeviceInfo *out, int max);
 
+/**
+ * Count connected XR50 devices from USB enumeration alone, without opening
+ * them: cheap enough for a presence poll. Returns -1 on error.
+ */
+int xv_device_count(void);
+
 /**
  * Open the first available XR50 device.
  * Returns NULL on error (check xv_last_error()).
@@ -160,6 +269,16 @@ const char *xv_device_version(const struct XvDevice *dev);
  */
 uint32_t xv_device_features(const struct XvDevice *dev);
 
+/**
+ * Get the full device info of an open device.
+ * Returns 0 on success, -1 if either pointer is null.
+ *
+ * # Safety
+ * `dev` must be a valid device pointer, or null.
+ * `out` must point to a writable `XvDeviceInfo`, or be null.
+ */
+int xv_device_info(const struct XvDevice *dev, struct XvDeviceInfo *out);
+
 /**
  * Start SLAM streaming.
  * `mode`: 0 = Edge, 1 = Mixed.
@@ -180,6 +299,29 @@ struct XvSlamStream *xv_start_slam(struct XvDevice *dev, int mode);
  */
 int xv_slam_recv(struct XvSlamStream *stream, struct XvPose *pose, int timeout_ms);
 
+/**
+ * Convert a pose from the XR50 device frame into another coordinate frame.
+ * `frame`: 0 = Device, 1 = OpenXR/OpenGL/Three.js (right-handed, -Z forward),
+ * 2 = Unity (left-handed, Z forward).
+ * `in_pose` and `out` may point to the same pose.
+ * Returns 0 on success, -1 on a null pointer or unknown frame.
+ *
+ * # Safety
+ * `in_pose` must point to a valid `XvPose` and `out` to a writable one, or be null.
+ */
+int xv_pose_to_frame(const struct XvPose *in_pose, int frame, struct XvPose *out);
+
+/**
+ * Rotation of `pose` as a unit axis, written to `axis[0..3]`, and an angle in
+ * radians within [0, π], written to `angle`. Near identity the axis is [1, 0, 0].
+ * Returns 0 on success, -1 on a null pointer.
+ *
+ * # Safety
+ * `pose` must point to a valid `XvPose`, `axis` to 3 writable doubles and
+ * `angle` to a writable double, or be null.
+ */
+int xv_pose_axis_angle(const struct XvPose *pose, double *axis, double *angle);
+
 /**
  * Check if the SLAM stream is still active.
  *
@@ -188,6 +330,14 @@ int xv_slam_recv(struct XvSlamStream *stream, struct XvPose *pose, int timeout_m
  */
 bool xv_slam_is_active(const struct XvSlamStream *stream);
 
+/**
+ * Check if the SLAM stream has stalled (device timestamp no longer advancing).
+ *
+ * # Safety
+ * `stream` must be a valid stream pointer, or null.
+ */
+bool xv_slam_is_stalled(const struct XvSlamStream *stream);
+
 /**
  * Stop a SLAM stream and free its resources.
  *
@@ -197,11 +347,18 @@ bool xv_slam_is_active(const struct XvSlamStream *stream);
 void xv_stop_slam(struct XvSlamStream *stream);
 
 /**
- * Get the last error message. Returns NULL if no error.
- * The returned pointer is valid until the next xvisio API call.
+ * Get the calling thread's last error message. Returns NULL if no error.
+ * The returned pointer is valid until the next xvisio API call on this thread.
  */
 const char *xv_last_error(void);
 
+/**
+ * Get the code of the calling thread's last error, one of the `XV_ERR_*`
+ * constants, or `XV_OK` if none has occurred. Set together with `xv_last_error`;
+ * calls that fail on a null argument leave both unchanged.
+ */
+int xv_last_error_code(void);
+
 #ifdef __cplusplus
 }  // extern "C"
 #endif  // __cplusplus
@@ -0,0 +1,162 @@
+/* RAII wrappers over xvisio.h for C++17. Hand-maintained: keep in step with src/ffi.rs. */
+
+#ifndef XVISIO_HPP
+#define XVISIO_HPP
+
+#include "xvisio.h"
+
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace xvisio {
+
+/** Failed call, carrying `xv_last_error` and its `XV_ERR_*` code. */
+class Error : public std::runtime_error {
+public:
+    /** Capture the calling thread's last error. */
+    Error()
+        : std::runtime_error(xv_last_error() ? xv_last_error() : "xvisio call failed"),
+          code_(xv_last_error_code()) {}
+
+    int code() const noexcept { return code_; }
+
+private:
+    int code_;
+};
+
+/**
+ * Owns an `XvSlamStream`, stopped in the destructor. Move-only.
+ * Independent of the `Device` it was started from, which may be closed first.
+ */
+class SlamStream {
+public:
+    /** Take ownership of `handle` from `xv_start_slam`. */
+    explicit SlamStream(XvSlamStream *handle) noexcept : handle_(handle) {}
+
+    SlamStream(SlamStream &&other) noexcept : handle_(std::exchange(other.handle_, nullptr)) {}
+
+    SlamStream &operator=(SlamStream &&other) noexcept {
+        if (this != &other) {
+            xv_stop_slam(handle_);
+            handle_ = std::exchange(other.handle_, nullptr);
+        }
+        return *this;
+    }
+
+    SlamStream(const SlamStream &) = delete;
+    SlamStream &operator=(const SlamStream &) = delete;
+
+    ~SlamStream() { xv_stop_slam(handle_); }
+
+    /**
+     * Receive the next pose into `pose`, see `xv_slam_recv` for `timeout_ms`.
+     * Returns false on timeout; throws `Error` once the stream has stopped.
+     */
+    bool recv(XvPose &pose, int timeout_ms = -1) {
+        if (xv_slam_recv(handle_, &pose, timeout_ms) == 0) {
+            return true;
+        }
+        if (xv_last_error_code() == XV_ERR_TIMEOUT) {
+            return false;
+        }
+        throw Error();
+    }
+
+    bool is_active() const noexcept { return xv_slam_is_active(handle_); }
+    bool is_stalled() const noexcept { return xv_slam_is_stalled(handle_); }
+
+    XvSlamStream *get() const noexcept { return handle_; }
+
+    /** Give up ownership; the caller must `xv_stop_slam` the result. */
+    XvSlamStream *release() noexcept { return std::exchange(handle_, nullptr); }
+
+private:
+    XvSlamStream *handle_;
+};
+
+/** Owns an `XvDevice`, closed in the destructor. Move-only. */
+class Device {
+public:
+    /** Take ownership of `handle` from `xv_open_first` or `xv_open_device`. */
+    explicit Device(XvDevice *handle) noexcept : handle_(handle) {}
+
+    Device(Device &&other) noexcept : handle_(std::exchange(other.handle_, nullptr)) {}
+
+    Device &operator=(Device &&other) noexcept {
+        if (this != &other) {
+            xv_close_device(handle_);
+            handle_ = std::exchange(other.handle_, nullptr);
+        }
+        return *this;
+    }
+
+    Device(const Device &) = delete;
+    Device &operator=(const Device &) = delete;
+
+    ~Device() { xv_close_device(handle_); }
+
+    /** Connected devices, see `xv_list_devices`. */
+    static std::vector<XvDeviceInfo> list() {
+        int count = xv_list_devices(nullptr, 0);
+        if (count < 0) {
+            throw Error();
+        }
+        std::vector<XvDeviceInfo> devices(count);
+        count = xv_list_devices(devices.data(), count);
+        if (count < 0) {
+            throw Error();
+        }
+        devices.resize(count);
+        return devices;
+    }
+
+    /** Connected devices without opening them, see `xv_device_count`. */
+    static int count() {
+        int count = xv_device_count();
+        if (count < 0) {
+            throw Error();
+        }
+        return count;
+    }
+
+    static Device open_first() { return Device(checked(xv_open_first())); }
+
+    static Device open(const XvDeviceInfo &info) { return Device(checked(xv_open_device(&info))); }
+
+    std::string uuid() const { return string(xv_device_uuid(handle_)); }
+    std::string version() const { return string(xv_device_version(handle_)); }
+    uint32_t features() const noexcept { return xv_device_features(handle_); }
+
+    XvDeviceInfo info() const {
+        XvDeviceInfo info{};
+        xv_device_info(handle_, &info);
+        return info;
+    }
+
+    /** Start SLAM streaming; `mode`: 0 = Edge, 1 = Mixed. */
+    SlamStream start_slam(int mode = 0) { return SlamStream(checked(xv_start_slam(handle_, mode))); }
+
+    XvDevice *get() const noexcept { return handle_; }
+
+    /** Give up ownership; the caller must `xv_close_device` the result. */
+    XvDevice *release() noexcept { return std::exchange(handle_, nullptr); }
+
+private:
+    static std::string string(const char *s) { return s ? s : ""; }
+
+    template <typename T>
+    static T *checked(T *handle) {
+        if (!handle) {
+            throw Error();
+        }
+        return handle;
+    }
+
+    XvDevice *handle_;
+};
+
+}  // namespace xvisio
+
+#endif  /* XVISIO_HPP */
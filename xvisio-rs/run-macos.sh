@@ -10,7 +10,7 @@
 #   ./run-macos.sh stream          # SLAM streaming
 #   ./run-macos.sh stream_json     # SLAM streaming with JSON output
 #   ./run-macos.sh server          # WebSocket SLAM server
-#   ./run-macos.sh macos_diag      # Low-level diagnostic
+#   ./run-macos.sh macos_diag      # SLAM start diagnostic
 #   ./run-macos.sh enumerate       # List devices (no sudo needed)
 #   ./run-macos.sh info            # Device info (no sudo needed)
 
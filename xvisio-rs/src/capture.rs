@@ -0,0 +1,124 @@
+//! Raw SLAM packet capture and replay for offline protocol analysis.
+//!
+//! File layout: the 8-byte [`MAGIC`], then one record per interrupt read:
+//! `f64 LE host timestamp (s) | u16 LE payload length | payload bytes`.
+//! Payloads are stored exactly as read, before any header checks, so the rusb
+//! backend's records lack the leading report ID.
+
+use crate::protocol::{self, SLAM_HEADER};
+use crate::types::SlamSample;
+use crate::{Result, XvisioError};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+pub const MAGIC: &[u8; 8] = b"XVRAW\x00\x00\x01";
+
+/// Appends raw packets to a capture file from the reader thread.
+pub(crate) struct CaptureWriter(BufWriter<File>);
+
+impl CaptureWriter {
+    pub(crate) fn create(path: &Path) -> Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        Ok(Self(out))
+    }
+
+    pub(crate) fn write(&mut self, host_timestamp_s: f64, data: &[u8]) -> io::Result<()> {
+        self.0.write_all(&host_timestamp_s.to_le_bytes())?;
+        self.0.write_all(&(data.len() as u16).to_le_bytes())?;
+        self.0.write_all(data)
+    }
+}
+
+/// One captured interrupt payload.
+#[derive(Debug, Clone)]
+pub struct RawRecord {
+    /// Seconds since the stream started, as seen by the reader thread.
+    pub host_timestamp_s: f64,
+    pub data: Vec<u8>,
+}
+
+impl RawRecord {
+    /// Re-parse the payload, restoring the report ID that interrupt reads omit.
+    pub fn sample(&self) -> Option<SlamSample> {
+        let mut sample = if self.data.starts_with(&SLAM_HEADER[1..]) {
+            let mut report = Vec::with_capacity(self.data.len() + 1);
+            report.push(SLAM_HEADER[0]);
+            report.extend_from_slice(&self.data);
+            protocol::parse_sample(&report)
+        } else {
+            protocol::parse_sample(&self.data)
+        }?;
+        sample.pose.host_timestamp_s = self.host_timestamp_s;
+        Some(sample)
+    }
+}
+
+/// Iterator over the records of a capture file, see [`replay_raw`].
+pub struct RawReplay(BufReader<File>);
+
+/// Open a capture written via [`SlamConfig::raw_capture`](crate::SlamConfig::raw_capture).
+pub fn replay_raw(path: impl AsRef<Path>) -> Result<RawReplay> {
+    let mut input = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not an xvisio raw capture").into());
+    }
+    Ok(RawReplay(input))
+}
+
+impl RawReplay {
+    fn read_record(&mut self) -> io::Result<Option<RawRecord>> {
+        let mut ts = [0u8; 8];
+        match self.0.read_exact(&mut ts) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let mut len = [0u8; 2];
+        self.0.read_exact(&mut len)?;
+        let mut data = vec![0u8; u16::from_le_bytes(len) as usize];
+        self.0.read_exact(&mut data)?;
+        Ok(Some(RawRecord {
+            host_timestamp_s: f64::from_le_bytes(ts),
+            data,
+        }))
+    }
+}
+
+impl Iterator for RawReplay {
+    type Item = Result<RawRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().map_err(XvisioError::from).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_round_trip() {
+        let path = std::env::temp_dir().join(format!("xvisio-capture-{}.bin", std::process::id()));
+        let mut report = [0u8; 63];
+        report[..3].copy_from_slice(&SLAM_HEADER);
+        report[3] = 7;
+        {
+            let mut writer = CaptureWriter::create(&path).unwrap();
+            writer.write(0.5, &report).unwrap();
+            writer.write(0.75, &report[1..]).unwrap();
+        }
+
+        let records: Vec<_> = replay_raw(&path).unwrap().map(|r| r.unwrap()).collect();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].data.len(), 62);
+        let sample = records[1].sample().unwrap();
+        assert_eq!(sample.pose.timestamp_us, 7);
+        assert_eq!(sample.pose.host_timestamp_s, 0.75);
+    }
+}
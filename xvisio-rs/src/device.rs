@@ -1,9 +1,30 @@
-use crate::hid::HidTransport;
-use crate::protocol::{PID, VID};
-use crate::slam::SlamStream;
-use crate::types::{DeviceInfo, Features, SlamMode};
+use crate::hid::{HidTransport, Transport};
+use crate::imu::ImuCalibration;
+use crate::protocol::{CommandBuilder, HID_USAGE_PAGE_VENDOR, PID, VID};
+use crate::slam::{
+    Backend, MacBackend, SlamConfig, SlamStream, StartupPhase, StartupProgress, StartupSequence,
+};
+use crate::types::{DeviceInfo, DeviceState, Features, Pose, SelfTestReport, SlamMode};
 use crate::{Result, XvisioError};
 use hidapi::HidApi;
+use std::time::{Duration, Instant};
+
+/// How long `read_state` listens for SLAM packets (~950 Hz, so dozens expected).
+const STATE_PROBE_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// How long `self_test` streams; covers startup and the camera warm-up.
+const SELF_TEST_DURATION: Duration = Duration::from_secs(2);
+/// Lowest sample rate `self_test` accepts.
+const SELF_TEST_MIN_RATE_HZ: f64 = 500.0;
+/// Accepted resting accelerometer magnitude in g. Wide because a stationary
+/// XR50 reads ~1.35 g (bias or scale unconfirmed, see PROTOCOL.md).
+const SELF_TEST_ACCEL_G: std::ops::RangeInclusive<f64> = 0.7..=1.6;
+
+/// How long `diagnose` streams each startup variant.
+const DIAGNOSE_DURATION: Duration = Duration::from_secs(3);
+
+/// Longest wait for each sample while `calibrate_imu_at_rest` collects.
+const CALIBRATION_SAMPLE_TIMEOUT: Duration = Duration::from_secs(1);
 
 /// Check if a hidapi DeviceInfo matches the XR50 HID interface.
 /// Interface 3 on Windows/Linux, -1 on macOS IOKit (only HID interface on the device).
@@ -13,6 +34,40 @@ fn is_xr50_hid(d: &hidapi::DeviceInfo) -> bool {
         && (d.interface_number() == 3 || d.interface_number() == -1)
 }
 
+/// Preference among matching HID collections, lower is better.
+/// Some Windows hosts expose several top-level collections on interface 3 and only
+/// the vendor-defined one answers commands. Backends that don't parse the report
+/// descriptor report usage page 0.
+fn collection_rank(d: &hidapi::DeviceInfo) -> u8 {
+    match d.usage_page() {
+        p if p >= HID_USAGE_PAGE_VENDOR => 0,
+        0 => 1,
+        _ => 2,
+    }
+}
+
+/// XR50 command collections, keeping only the best-ranked kind so a device that
+/// exposes several collections is listed once.
+fn xr50_command_collections(api: &HidApi) -> Vec<&hidapi::DeviceInfo> {
+    let matches: Vec<_> = api.device_list().filter(|d| is_xr50_hid(d)).collect();
+    let best = matches.iter().map(|d| collection_rank(d)).min();
+    matches
+        .into_iter()
+        .filter(|d| Some(collection_rank(d)) == best)
+        .collect()
+}
+
+/// First XR50 command collection, if any is connected.
+fn find_xr50_hid(api: &HidApi) -> Option<&hidapi::DeviceInfo> {
+    let hid_info = xr50_command_collections(api).into_iter().next()?;
+    log::debug!(
+        "Selected XR50 HID collection usage_page=0x{:04x} usage=0x{:04x}",
+        hid_info.usage_page(),
+        hid_info.usage()
+    );
+    Some(hid_info)
+}
+
 fn create_hid_api() -> Result<HidApi> {
     let api = HidApi::new()?;
     #[cfg(target_os = "macos")]
@@ -23,108 +78,443 @@ fn create_hid_api() -> Result<HidApi> {
     Ok(api)
 }
 
+/// Per-device query attempts in `list_devices`; the first open often fails on a busy bus.
+const QUERY_ATTEMPTS: usize = 3;
+const QUERY_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Re-open attempts during `start_slam` and after a failed one; covers a USB
+/// re-enumeration.
+const REOPEN_ATTEMPTS: usize = 40;
+const REOPEN_DELAY: Duration = Duration::from_millis(100);
+
+/// Stream stops and re-reads when opening finds the identity unreadable, e.g. a
+/// crashed session left edge streaming on and SLAM packets answer the queries.
+const IDENTITY_RETRIES: u32 = 2;
+
 /// List all connected XR50 devices with their info.
 ///
 /// Opens each device temporarily to read UUID, version, and features, then closes it.
+/// Devices are ordered by HID path, so indices are stable while the set of connected
+/// devices doesn't change. A device that can't be queried is still listed, with
+/// empty UUID/version and the failure in `error`.
 pub fn list_devices() -> Result<Vec<DeviceInfo>> {
     let api = create_hid_api()?;
-    let mut devices = Vec::new();
+    let mut collections = xr50_command_collections(&api);
+    collections.sort_by(|a, b| a.path().cmp(b.path()));
 
-    for dev_info in api.device_list() {
-        if !is_xr50_hid(dev_info) {
-            continue;
-        }
+    Ok(collections
+        .into_iter()
+        .map(|hid_info| query_device_info_with_retry(&api, hid_info))
+        .collect())
+}
+
+/// List connected XR50 devices from HID enumeration alone, without opening them.
+///
+/// Cheap and safe to call while another process streams, but only the USB-level
+/// fields (`bus_id`, bus number and address, HID usage) are filled in; UUID and
+/// version are empty and features unset. Ordered like [`list_devices`].
+pub fn list_devices_fast() -> Result<Vec<DeviceInfo>> {
+    let api = create_hid_api()?;
+    let mut collections = xr50_command_collections(&api);
+    collections.sort_by(|a, b| a.path().cmp(b.path()));
+    Ok(collections.into_iter().map(hid_device_info).collect())
+}
 
-        match query_device_info(&api, dev_info) {
-            Ok(info) => devices.push(info),
+fn query_device_info_with_retry(api: &HidApi, hid_info: &hidapi::DeviceInfo) -> DeviceInfo {
+    let mut attempt = 1;
+    loop {
+        match query_device_info(api, hid_info) {
+            Ok(info) => return info,
+            Err(e) if attempt < QUERY_ATTEMPTS => {
+                log::debug!(
+                    "Query of {:?} failed (attempt {}): {}",
+                    hid_info.path(),
+                    attempt,
+                    e
+                );
+                attempt += 1;
+                std::thread::sleep(QUERY_RETRY_DELAY);
+            }
             Err(e) => {
-                log::warn!("Failed to query device at {:?}: {}", dev_info.path(), e);
+                log::warn!("Failed to query device at {:?}: {}", hid_info.path(), e);
+                return DeviceInfo {
+                    error: Some(e.to_string()),
+                    ..hid_device_info(hid_info)
+                };
             }
         }
     }
-
-    Ok(devices)
 }
 
 /// Query device info by opening it temporarily.
 fn query_device_info(api: &HidApi, hid_info: &hidapi::DeviceInfo) -> Result<DeviceInfo> {
     let device = api.open_path(hid_info.path())?;
-    let hid = HidTransport::new(device);
+    read_identity(&HidTransport::new(device), hid_device_info(hid_info))
+}
+
+/// Fill in UUID, version and features by querying an open transport.
+fn read_identity<T: Transport>(hid: &HidTransport<T>, base: DeviceInfo) -> Result<DeviceInfo> {
     let uuid = hid.read_uuid()?;
     let version = hid.read_version()?;
-    let features = hid.read_features()?;
-
+    check_supported(&uuid, &version)?;
     Ok(DeviceInfo {
         uuid,
         version,
-        features,
-        bus_id: hid_info.path().to_str().unwrap_or("").to_string(),
-        device_address: 0,
+        features: hid.read_features()?,
+        ..base
     })
 }
 
+/// Reject identities of a different device, e.g. a sibling sharing the VID/PID
+/// with a different protocol. Only a version whose product field names another
+/// product counts (`1V1.04P31||xr50|V1.09|...`); a version without one is
+/// opened with a warning. `XVISIO_ALLOW_UNKNOWN_DEVICE=1` skips the check.
+fn check_supported(uuid: &str, version: &str) -> Result<()> {
+    let product = version
+        .split_once("||")
+        .and_then(|(_, rest)| rest.split('|').next())
+        .filter(|product| !product.is_empty());
+    match product {
+        Some(product) if product.eq_ignore_ascii_case("xr50") => Ok(()),
+        Some(_) if !Device::read_env_bool("XVISIO_ALLOW_UNKNOWN_DEVICE", false) => {
+            Err(XvisioError::UnsupportedDevice {
+                uuid: uuid.to_string(),
+                version: version.to_string(),
+            })
+        }
+        _ => {
+            log::warn!(
+                "Can't confirm an XR50 from version {:?}, opening anyway",
+                version
+            );
+            Ok(())
+        }
+    }
+}
+
+/// DeviceInfo with only the fields known from enumeration filled in.
+fn hid_device_info(hid_info: &hidapi::DeviceInfo) -> DeviceInfo {
+    let path = hid_info.path().to_str().unwrap_or("");
+    let (bus_number, device_address) = usb_location(path).unwrap_or((0, 0));
+    DeviceInfo {
+        uuid: String::new(),
+        version: String::new(),
+        features: Features::empty(),
+        bus_id: path.to_string(),
+        bus_number,
+        device_address,
+        usage_page: hid_info.usage_page(),
+        usage: hid_info.usage(),
+        serial_number: non_empty(hid_info.serial_number()),
+        error: None,
+    }
+}
+
+/// Serial number strings come back empty rather than absent from some backends.
+fn non_empty(s: Option<&str>) -> Option<String> {
+    s.filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+/// USB (bus number, address) of the device behind a HID path.
+///
+/// Tried in order: a libusb-style `bus:addr:iface` path, the Linux hidraw sysfs
+/// tree, then rusb enumeration if exactly one XR50 is connected.
+fn usb_location(path: &str) -> Option<(u8, u8)> {
+    libusb_path_location(path)
+        .or_else(|| sysfs_location(path))
+        .or_else(single_xr50_location)
+}
+
+/// Parse hidapi's libusb backend path format `%04x:%04x:%02x` (bus, address, interface).
+fn libusb_path_location(path: &str) -> Option<(u8, u8)> {
+    let mut parts = path.split(':');
+    let bus = u8::from_str_radix(parts.next()?, 16).ok()?;
+    let address = u8::from_str_radix(parts.next()?, 16).ok()?;
+    u8::from_str_radix(parts.next()?, 16).ok()?;
+    parts.next().is_none().then_some((bus, address))
+}
+
+/// Walk up from `/sys/class/hidraw/<node>/device` to the USB device's busnum/devnum.
+#[cfg(target_os = "linux")]
+fn sysfs_location(path: &str) -> Option<(u8, u8)> {
+    let node = std::path::Path::new(path).file_name()?.to_str()?;
+    let device = std::fs::canonicalize(format!("/sys/class/hidraw/{}/device", node)).ok()?;
+    let read = |dir: &std::path::Path, name: &str| -> Option<u8> {
+        std::fs::read_to_string(dir.join(name))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    };
+    device
+        .ancestors()
+        .find_map(|dir| Some((read(dir, "busnum")?, read(dir, "devnum")?)))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sysfs_location(_path: &str) -> Option<(u8, u8)> {
+    None
+}
+
+#[cfg(feature = "usb")]
+fn single_xr50_location() -> Option<(u8, u8)> {
+    let devices = rusb::devices().ok()?;
+    let mut xr50 = devices.iter().filter(|d| {
+        d.device_descriptor()
+            .map(|desc| desc.vendor_id() == VID && desc.product_id() == PID)
+            .unwrap_or(false)
+    });
+    let device = xr50.next()?;
+    xr50.next()
+        .is_none()
+        .then(|| (device.bus_number(), device.address()))
+}
+
+#[cfg(not(feature = "usb"))]
+fn single_xr50_location() -> Option<(u8, u8)> {
+    None
+}
+
+/// USB link of a device, see [`Device::usb_info`].
+#[cfg(feature = "usb")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsbInfo {
+    pub bus_number: u8,
+    pub device_address: u8,
+    /// Negotiated link speed.
+    pub speed: rusb::Speed,
+}
+
+#[cfg(feature = "usb")]
+impl UsbInfo {
+    /// Whether the device enumerated below High Speed: Full Speed allows one
+    /// interrupt transfer per 1 ms frame, no headroom for the ~950 Hz stream.
+    pub fn is_slow(&self) -> bool {
+        matches!(self.speed, rusb::Speed::Low | rusb::Speed::Full)
+    }
+}
+
+/// One start tried by [`Device::diagnose`].
+#[derive(Debug, Clone)]
+pub struct DiagnosticAttempt {
+    /// Backend requested; macOS picks its own, see [`SlamConfig::mac_backend`].
+    pub backend: Backend,
+    /// Startup sequence variant, e.g. "stereo init".
+    pub variant: &'static str,
+    /// Why the stream didn't start, if it didn't.
+    pub error: Option<String>,
+    pub samples: u64,
+    /// Samples with a non-identity pose, i.e. the cameras feed the SLAM.
+    pub tracking_samples: u64,
+    /// Sample rate measured from the first sample on (nominally ~950 Hz).
+    pub sample_rate_hz: f64,
+}
+
+/// Result of [`Device::diagnose`]: the starts tried, in order.
+#[derive(Debug, Clone)]
+pub struct DiagnosticReport {
+    pub attempts: Vec<DiagnosticAttempt>,
+}
+
+impl DiagnosticReport {
+    /// The attempt that reached tracking; the diagnosis stops at the first one.
+    pub fn tracking(&self) -> Option<&DiagnosticAttempt> {
+        self.attempts.iter().find(|a| a.tracking_samples > 0)
+    }
+}
+
+/// What a stream delivered within a fixed time.
+struct StreamProbe {
+    samples: u64,
+    tracking_samples: u64,
+    sample_rate_hz: f64,
+    /// Mean accelerometer magnitude in g, `None` without IMU data.
+    accel_g: Option<f64>,
+}
+
+impl StreamProbe {
+    /// Read `stream` for `duration`.
+    fn run(stream: &SlamStream, duration: Duration) -> StreamProbe {
+        let deadline = Instant::now() + duration;
+        let mut first = None;
+        let (mut samples, mut tracking_samples) = (0u64, 0u64);
+        let (mut accel_sum, mut accel_samples) = (0.0, 0u32);
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            let Ok(sample) = stream.recv_timeout(remaining) else {
+                break;
+            };
+            first.get_or_insert_with(Instant::now);
+            samples += 1;
+            tracking_samples += u64::from(!sample.pose.is_identity());
+            if let Some(imu) = sample.imu {
+                accel_sum += imu.accelerometer.iter().map(|a| a * a).sum::<f64>().sqrt();
+                accel_samples += 1;
+            }
+        }
+        StreamProbe {
+            samples,
+            tracking_samples,
+            sample_rate_hz: first.map_or(0.0, |t| samples as f64 / t.elapsed().as_secs_f64()),
+            accel_g: (accel_samples > 0).then(|| accel_sum / accel_samples as f64),
+        }
+    }
+}
+
 /// An opened XR50 device ready for queries and SLAM streaming.
 pub struct Device {
     /// HidApi keeps the IOKit run loop alive on macOS for commands.
     api: Option<HidApi>,
     hid: Option<HidTransport>,
-    /// Path for opening a second handle for SLAM streaming.
+    /// libusb command handle of a device opened with [`Device::open_from_fd`].
+    #[cfg(feature = "usb")]
+    usb: Option<HidTransport<rusb::DeviceHandle<rusb::GlobalContext>>>,
+    /// Path for opening a second handle for SLAM streaming; empty when opened from
+    /// a file descriptor, which leaves nothing to re-open.
     device_path: std::ffi::CString,
+    /// Mode of the last successful `start_slam`.
+    mode: Option<SlamMode>,
     uuid: String,
     version: String,
     features: Features,
+    serial_number: Option<String>,
+    /// HID usage page and usage of the opened collection (0 without hidapi).
+    usage_page: u16,
+    usage: u16,
 }
 
 impl Device {
     /// Open the first available XR50 device.
     pub fn open_first() -> Result<Device> {
         let api = create_hid_api()?;
+        let device_path = find_xr50_hid(&api)
+            .ok_or(XvisioError::DeviceNotFound)?
+            .path()
+            .to_owned();
+        Self::open_path(api, device_path)
+    }
 
-        let hid_info = api
-            .device_list()
-            .find(|d| is_xr50_hid(d))
-            .ok_or(XvisioError::DeviceNotFound)?;
+    /// Open a specific device by DeviceInfo.
+    ///
+    /// Matches on the HID path in `bus_id`, or on USB bus number and address when
+    /// those are set (the path may be truncated, e.g. in the FFI struct). A non-zero
+    /// `usage_page` also selects the collection with that usage page and usage.
+    pub fn open(info: &DeviceInfo) -> Result<Device> {
+        let api = create_hid_api()?;
+        let matches = |d: &hidapi::DeviceInfo| {
+            let path = d.path().to_str().unwrap_or("");
+            let collection = info.usage_page == 0
+                || (d.usage_page(), d.usage()) == (info.usage_page, info.usage);
+            collection
+                && (path == info.bus_id
+                    || (info.device_address != 0
+                        && usb_location(path) == Some((info.bus_number, info.device_address))))
+        };
+        let device_path = xr50_command_collections(&api)
+            .into_iter()
+            .find(|d| matches(d))
+            .ok_or(XvisioError::DeviceNotFound)?
+            .path()
+            .to_owned();
+        Self::open_path(api, device_path)
+    }
 
-        let device_path = hid_info.path().to_owned();
+    /// Open the HID path and read the device identity.
+    ///
+    /// A device left streaming by a crashed process doesn't answer the UUID query,
+    /// so on failure the stream is stopped and the queries retried.
+    fn open_path(api: HidApi, device_path: std::ffi::CString) -> Result<Device> {
+        let (usage_page, usage) = api
+            .device_list()
+            .find(|d| d.path() == device_path.as_c_str())
+            .map_or((0, 0), |d| (d.usage_page(), d.usage()));
         let device = api.open_path(&device_path)?;
+        let serial_number = non_empty(device.get_serial_number_string().ok().flatten().as_deref());
         let hid = HidTransport::new(device);
-
-        let uuid = hid.read_uuid()?;
-        let version = hid.read_version()?;
-        let features = hid.read_features()?;
-
-        log::info!(
-            "Opened XR50: UUID={} Version={} Features={:?}",
-            uuid,
-            version,
-            features
-        );
-
+        let (uuid, version, features) = Self::read_device_identity(&hid)?;
         Ok(Device {
             api: Some(api),
             hid: Some(hid),
+            #[cfg(feature = "usb")]
+            usb: None,
             device_path,
+            mode: None,
             uuid,
             version,
             features,
+            serial_number,
+            usage_page,
+            usage,
         })
     }
 
-    /// Open a specific device by DeviceInfo.
-    pub fn open(info: &DeviceInfo) -> Result<Device> {
-        let api = create_hid_api()?;
-
-        let hid_info = api
-            .device_list()
-            .find(|d| is_xr50_hid(d) && d.path().to_str().unwrap_or("") == info.bus_id)
-            .ok_or(XvisioError::DeviceNotFound)?;
-
-        let device_path = hid_info.path().to_owned();
-        let device = api.open_path(&device_path)?;
-        let hid = HidTransport::new(device);
+    /// Open the XR50 behind an already opened USB device file descriptor, e.g. the
+    /// one Android's `UsbDeviceConnection::getFileDescriptor` returns once the
+    /// user granted permission. hidapi can't enumerate under that model, so the
+    /// device is driven through libusb like the macOS rusb backend: starting SLAM
+    /// hands the handle to the reader, and commands fail until the device is
+    /// opened again.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open usbfs file descriptor of the XR50 and stay open for
+    /// as long as the `Device` and any stream started from it.
+    #[cfg(all(feature = "usb", unix))]
+    pub unsafe fn open_from_fd(fd: std::os::unix::io::RawFd) -> Result<Device> {
+        use rusb::UsbContext;
+
+        // libusb can't scan the bus without root on Android; wrapping the fd
+        // needs no scan, as long as discovery is off before the context exists.
+        #[cfg(target_os = "android")]
+        rusb::disable_device_discovery()?;
+        let handle = rusb::GlobalContext::default().open_device_with_fd(fd)?;
+        handle.claim_interface(crate::protocol::HID_INTERFACE)?;
+        let serial_number = handle
+            .device()
+            .device_descriptor()
+            .ok()
+            .and_then(|d| handle.read_serial_number_string_ascii(&d).ok());
+        let usb = HidTransport::new(handle);
+        let (uuid, version, features) = Self::read_device_identity(&usb)?;
+        Ok(Device {
+            api: None,
+            hid: None,
+            usb: Some(usb),
+            device_path: std::ffi::CString::default(),
+            mode: None,
+            uuid,
+            version,
+            features,
+            serial_number: non_empty(serial_number.as_deref()),
+            usage_page: 0,
+            usage: 0,
+        })
+    }
 
-        let uuid = hid.read_uuid()?;
-        let version = hid.read_version()?;
+    /// Read and check UUID, version and features after opening.
+    fn read_device_identity<T: Transport + ?Sized>(
+        hid: &HidTransport<T>,
+    ) -> Result<(String, String, Features)> {
+        let mut retries = 0;
+        let (uuid, version) = loop {
+            match hid
+                .read_uuid()
+                .and_then(|uuid| Ok((uuid, hid.read_version()?)))
+            {
+                Ok(identity) => break identity,
+                Err(e) if retries < IDENTITY_RETRIES => {
+                    retries += 1;
+                    log::warn!(
+                        "Identity read failed ({}), stopping a possibly stale stream (retry {}/{})",
+                        e,
+                        retries,
+                        IDENTITY_RETRIES
+                    );
+                    hid.reset()?;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        check_supported(&uuid, &version)?;
         let features = hid.read_features()?;
 
         log::info!(
@@ -133,15 +523,7 @@ impl Device {
             version,
             features
         );
-
-        Ok(Device {
-            api: Some(api),
-            hid: Some(hid),
-            device_path,
-            uuid,
-            version,
-            features,
-        })
+        Ok((uuid, version, features))
     }
 
     /// Get the device UUID.
@@ -154,17 +536,306 @@ impl Device {
         &self.version
     }
 
+    /// Raw HID report descriptor of the command interface, for diagnosing variant
+    /// hardware whose reports may not be the 63 bytes this crate assumes. Its
+    /// length is the `Vec`'s.
+    pub fn report_descriptor(&self) -> Result<Vec<u8>> {
+        self.hid()?.report_descriptor()
+    }
+
+    /// USB serial number string, see [`DeviceInfo::serial_number`].
+    pub fn serial_number(&self) -> Option<&str> {
+        self.serial_number.as_deref()
+    }
+
     /// Get the device feature flags.
     pub fn features(&self) -> Features {
         self.features
     }
 
+    /// The mode this `Device` last started SLAM in, `None` before the first start.
+    ///
+    /// The firmware can't be asked for its configured mode, so this only knows
+    /// what this handle requested. A stream left running by another process
+    /// shows up in [`read_state`](Self::read_state) instead.
+    pub fn current_mode(&self) -> Option<SlamMode> {
+        self.mode
+    }
+
+    /// Describe the device's UVC camera interfaces, if any, for use with a
+    /// separate UVC capture library. See [`crate::uvc`].
+    ///
+    /// A device opened with [`Device::open_from_fd`] is found only when it is the
+    /// one XR50 connected.
+    #[cfg(feature = "usb")]
+    pub fn uvc_info(&self) -> Result<Option<crate::uvc::UvcInfo>> {
+        crate::uvc::query_device(&self.usb_device()?)
+    }
+
+    /// Bus, address and negotiated link speed, e.g. to warn that the device sits
+    /// on a slow port or hub.
+    ///
+    /// A device opened with [`Device::open_from_fd`] is found only when it is the
+    /// one XR50 connected.
+    #[cfg(feature = "usb")]
+    pub fn usb_info(&self) -> Result<UsbInfo> {
+        let device = self.usb_device()?;
+        Ok(UsbInfo {
+            bus_number: device.bus_number(),
+            device_address: device.address(),
+            speed: device.speed(),
+        })
+    }
+
+    /// The rusb device behind this handle's HID path.
+    #[cfg(feature = "usb")]
+    fn usb_device(&self) -> Result<rusb::Device<rusb::GlobalContext>> {
+        let (bus, address) = usb_location(self.device_path.to_str().unwrap_or(""))
+            .ok_or(XvisioError::DeviceNotFound)?;
+        rusb::devices()?
+            .iter()
+            .find(|d| d.bus_number() == bus && d.address() == address)
+            .ok_or(XvisioError::DeviceNotFound)
+    }
+
+    /// Get the identity of this device as a `DeviceInfo`.
+    pub fn info(&self) -> DeviceInfo {
+        let path = self.device_path.to_str().unwrap_or("");
+        let (bus_number, device_address) = usb_location(path).unwrap_or((0, 0));
+        DeviceInfo {
+            uuid: self.uuid.clone(),
+            version: self.version.clone(),
+            features: self.features,
+            bus_id: path.to_string(),
+            bus_number,
+            device_address,
+            usage_page: self.usage_page,
+            usage: self.usage,
+            serial_number: self.serial_number.clone(),
+            error: None,
+        }
+    }
+
+    fn hid(&self) -> Result<&HidTransport<dyn Transport>> {
+        #[cfg(feature = "usb")]
+        if let Some(usb) = &self.usb {
+            return Ok(usb);
+        }
+        match &self.hid {
+            Some(hid) => Ok(hid),
+            None => Err(XvisioError::HidCommand(
+                "Device handle consumed by SLAM".into(),
+            )),
+        }
+    }
+
     /// Send a raw HID command and return the response.
     pub fn hid_command(&self, cmd: &[u8]) -> Result<Vec<u8>> {
-        self.hid
-            .as_ref()
-            .ok_or_else(|| XvisioError::HidCommand("Device handle consumed by SLAM".into()))?
-            .transaction(cmd)
+        self.hid()?.transaction(cmd)
+    }
+
+    /// Send a command and return the response payload, the bytes after the echo.
+    ///
+    /// Fails with [`XvisioError::StillStreaming`] when a SLAM packet answers, and
+    /// as [`Response::check`](crate::protocol::Response::check) does when the
+    /// echo is missing or doesn't match.
+    pub fn send_command(&self, cmd: &CommandBuilder) -> Result<Vec<u8>> {
+        let report = self.hid()?.transaction(cmd.bytes())?;
+        let response = cmd.response(&report);
+        if response.is_slam_packet() {
+            return Err(XvisioError::StillStreaming);
+        }
+        Ok(response.check()?.payload.to_vec())
+    }
+
+    /// Save the SLAM map held by the device.
+    ///
+    /// No HID command for this is known, so this always fails with
+    /// [`XvisioError::Unsupported`]: the official SDK saves and loads maps in its
+    /// host-side CSLAM module, not on the device (see PROTOCOL.md).
+    pub fn save_map(&self) -> Result<Vec<u8>> {
+        Err(XvisioError::Unsupported("on-device map save"))
+    }
+
+    /// Load a map saved with [`save_map`](Self::save_map) for re-localization.
+    /// Always fails with [`XvisioError::Unsupported`], for the same reason.
+    pub fn load_map(&self, _map: &[u8]) -> Result<()> {
+        Err(XvisioError::Unsupported("on-device map load"))
+    }
+
+    /// Probe the device's runtime state.
+    ///
+    /// The firmware has no status command, so streaming is detected by listening
+    /// briefly for SLAM packets on the interrupt pipe.
+    pub fn read_state(&self) -> Result<DeviceState> {
+        Ok(DeviceState {
+            streaming: self.hid()?.is_streaming(STATE_PROBE_TIMEOUT)?,
+        })
+    }
+
+    /// Stop any active edge stream and discard queued packets, returning the
+    /// device to command mode (e.g. after a crash left it streaming).
+    pub fn reset(&self) -> Result<()> {
+        self.hid()?.reset()
+    }
+
+    /// Send the edge stream stop command, halting the firmware stream while a
+    /// [`SlamStream`] reader keeps running (its channel just runs dry).
+    ///
+    /// Packets already in flight stay queued on the pipe; before info queries use
+    /// [`reset`](Self::reset), which also drains them.
+    pub fn stop_streaming(&self) -> Result<()> {
+        self.hid()?.edge_stream(false)
+    }
+
+    /// Start the edge stream, wait for the first tracking (non-identity) pose, then
+    /// stop the reader and the edge stream again. For one-off probes and health checks.
+    ///
+    /// `timeout` covers startup too. On the macOS rusb backend, where the stream
+    /// consumes the command handle, the handle is re-opened afterwards.
+    pub fn read_pose_once(&mut self, timeout: Duration) -> Result<Pose> {
+        let deadline = std::time::Instant::now() + timeout;
+        let stream = self.start_slam(SlamMode::Edge)?;
+        let pose = loop {
+            let Some(remaining) = deadline.checked_duration_since(std::time::Instant::now()) else {
+                break Err(XvisioError::FirstSampleTimeout(timeout));
+            };
+            match stream.recv_timeout(remaining) {
+                Ok(sample) if !sample.pose.is_identity() => break Ok(sample.pose),
+                Ok(_) => {}
+                Err(XvisioError::Timeout) => break Err(XvisioError::FirstSampleTimeout(timeout)),
+                Err(e) => break Err(e),
+            }
+        };
+        self.end_probe(stream);
+        pose
+    }
+
+    /// Check the device is healthy before a session: re-read the identity, stream
+    /// edge SLAM briefly and judge the sample rate, camera tracking and the resting
+    /// accelerometer. Keep the device still while it runs.
+    ///
+    /// Failed checks are reported, not returned as errors; `Err` means the test
+    /// couldn't run, e.g. the stream didn't start.
+    pub fn self_test(&mut self) -> Result<SelfTestReport> {
+        let identity = self.hid().is_ok_and(|hid| {
+            hid.read_uuid().is_ok_and(|uuid| uuid == self.uuid)
+                && hid.read_version().is_ok_and(|v| v == self.version)
+                && hid.read_features().is_ok_and(|f| f == self.features)
+        });
+
+        let stream = self.start_slam(SlamMode::Edge)?;
+        let probe = StreamProbe::run(&stream, SELF_TEST_DURATION);
+        self.end_probe(stream);
+
+        Ok(SelfTestReport {
+            identity,
+            streaming: probe.samples > 0,
+            sample_rate_hz: probe.sample_rate_hz,
+            rate_ok: probe.sample_rate_hz >= SELF_TEST_MIN_RATE_HZ,
+            cameras: probe.tracking_samples > 0,
+            accel_g: probe.accel_g,
+            accel_ok: probe
+                .accel_g
+                .is_some_and(|g| SELF_TEST_ACCEL_G.contains(&g)),
+        })
+    }
+
+    /// Find a way to get tracking on this host: start edge SLAM with a few
+    /// backend and startup sequence variants, each streamed for 3 s, until one
+    /// delivers non-identity poses. Move the device while it runs.
+    ///
+    /// Tries the platform's built-in sequence, then with stereo camera init, then
+    /// UVC mode 1, then (off macOS) the libusb backend. Failed starts are recorded
+    /// in the report; `Err` only if the command handle couldn't be restored
+    /// between attempts.
+    pub fn diagnose(&mut self) -> Result<DiagnosticReport> {
+        let sequence = |uvc_mode, stereo_init| {
+            let mut sequence = StartupSequence::standard(true, uvc_mode, false, true, false);
+            // As in the built-in macOS sequences, which re-open after configure.
+            sequence.steps[0].reopen_after = cfg!(target_os = "macos");
+            if stereo_init {
+                sequence = sequence.with_stereo_init(false);
+            }
+            sequence
+        };
+        let mut variants = vec![
+            (Backend::Hidapi, "built-in", None),
+            (Backend::Hidapi, "stereo init", Some(sequence(0, true))),
+            (Backend::Hidapi, "UVC mode 1", Some(sequence(1, false))),
+        ];
+        if cfg!(all(feature = "usb", not(target_os = "macos"))) {
+            variants.push((Backend::Rusb, "built-in", None));
+        }
+
+        let mut report = DiagnosticReport {
+            attempts: Vec::new(),
+        };
+        for (backend, variant, startup_sequence) in variants {
+            log::info!("Diagnose: {:?} backend, {} sequence", backend, variant);
+            let mut config = SlamConfig::new(SlamMode::Edge).backend(backend);
+            config.startup_sequence = startup_sequence;
+            let mut attempt = DiagnosticAttempt {
+                backend,
+                variant,
+                error: None,
+                samples: 0,
+                tracking_samples: 0,
+                sample_rate_hz: 0.0,
+            };
+            match self.start_slam_with_config(&config) {
+                Ok(stream) => {
+                    let probe = StreamProbe::run(&stream, DIAGNOSE_DURATION);
+                    self.end_probe(stream);
+                    attempt.samples = probe.samples;
+                    attempt.tracking_samples = probe.tracking_samples;
+                    attempt.sample_rate_hz = probe.sample_rate_hz;
+                }
+                Err(e) => attempt.error = Some(e.to_string()),
+            }
+            report.attempts.push(attempt);
+            if report.tracking().is_some() {
+                break;
+            }
+            self.hid()?;
+        }
+        Ok(report)
+    }
+
+    /// Stop a probe's reader and its edge stream, re-opening the command handle
+    /// if the stream consumed it. Every probe ends here.
+    fn end_probe(&mut self, stream: SlamStream) {
+        stream.stop();
+        if self.hid.is_some() {
+            if let Err(e) = self.reset() {
+                log::warn!("Failed to stop edge stream after probe: {}", e);
+            }
+        } else if !self.device_path.is_empty() {
+            self.restore_handles();
+        }
+    }
+
+    /// Stream edge SLAM until `samples` IMU readings are collected and derive an
+    /// [`ImuCalibration`] from them, see [`ImuCalibration::at_rest`]. The device
+    /// must stay still throughout; at ~950 Hz, 1000 samples take about a second.
+    ///
+    /// Pass the result to [`SlamConfig::imu_calibration`]; storing it between runs
+    /// is up to the caller.
+    pub fn calibrate_imu_at_rest(&mut self, samples: usize) -> Result<ImuCalibration> {
+        let stream = self.start_slam(SlamMode::Edge)?;
+        let mut readings = Vec::with_capacity(samples);
+        let result = loop {
+            if readings.len() >= samples.max(1) {
+                break Ok(());
+            }
+            match stream.recv_timeout(CALIBRATION_SAMPLE_TIMEOUT) {
+                Ok(sample) => readings.extend(sample.imu),
+                Err(e) => break Err(e),
+            }
+        };
+        self.end_probe(stream);
+        result?;
+        ImuCalibration::at_rest(&readings).ok_or(XvisioError::AccelerometerZero)
     }
 
     /// Start SLAM streaming in the specified mode.
@@ -174,64 +845,220 @@ impl Device {
     /// interrupt reading, because macOS IOKit can't handle the XR50's USB
     /// re-enumeration during mode changes.
     pub fn start_slam(&mut self, mode: SlamMode) -> Result<SlamStream> {
-        let (edge, embedded_algo) = match mode {
-            SlamMode::Edge => (true, false),
-            SlamMode::Mixed => (false, true),
+        self.start_slam_with_config(&SlamConfig::new(mode))
+    }
+
+    /// Start SLAM streaming with explicit options, see [`SlamConfig`].
+    ///
+    /// The macOS retry loops can take several seconds; set
+    /// [`SlamConfig::cancel_token`] to abort them with [`XvisioError::Cancelled`].
+    ///
+    /// If the start fails after the command handle was handed over (macOS), it is
+    /// re-opened before returning the error so the `Device` can be used again.
+    pub fn start_slam_with_config(&mut self, config: &SlamConfig) -> Result<SlamStream> {
+        config.check_cancelled()?;
+        let began = Instant::now();
+        let progress = StartupProgress::new(config);
+        // A `None` UVC mode leaves the choice to the backend default / XVISIO_UVC_MODE.
+        let (edge, uvc_mode, embedded_algo) = match config.mode {
+            SlamMode::Edge => (true, None, false),
+            SlamMode::Mixed => (false, None, true),
+            SlamMode::Custom {
+                edge,
+                uvc_mode,
+                embedded_algo,
+            } => (edge, Some(uvc_mode), embedded_algo),
         };
 
-        if cfg!(target_os = "macos") {
-            match Self::read_env_string("XVISIO_MAC_BACKEND", "rusb").as_str() {
-                "hidapi" => {
-                    log::info!("macOS backend: hidapi");
-                    self.start_slam_hidapi(edge, embedded_algo)
-                }
-                "rusb" => {
-                    log::info!("macOS backend: rusb");
-                    self.start_slam_rusb(edge, embedded_algo)
-                }
-                other => {
-                    log::warn!(
-                        "Unknown XVISIO_MAC_BACKEND='{}', using rusb (supported: rusb|hidapi)",
-                        other
-                    );
-                    self.start_slam_rusb(edge, embedded_algo)
+        #[cfg(feature = "usb")]
+        if let Some(usb) = self.usb.take() {
+            let mut stream = Self::start_slam_usb(usb, &progress, edge, uvc_mode, embedded_algo)?;
+            stream.record_startup(began, progress.into_phases());
+            self.mode = Some(config.mode);
+            return Ok(stream);
+        }
+        let result = if cfg!(target_os = "macos") {
+            let default = if cfg!(feature = "usb") {
+                MacBackend::Rusb
+            } else {
+                MacBackend::IokitNoDetach
+            };
+            let backend = config.mac_backend.unwrap_or_else(|| {
+                let value = Self::read_env_string("XVISIO_MAC_BACKEND", "");
+                MacBackend::parse(&value).unwrap_or_else(|| {
+                    if !value.is_empty() {
+                        log::warn!(
+                            "Unknown XVISIO_MAC_BACKEND='{}', using {:?} (supported: rusb|hidapi|iokit)",
+                            value,
+                            default
+                        );
+                    }
+                    default
+                })
+            });
+            log::info!("macOS backend: {:?}", backend);
+            match backend {
+                MacBackend::IokitNoDetach => {
+                    self.start_slam_hidapi(&progress, edge, uvc_mode, embedded_algo)
                 }
+                #[cfg(feature = "usb")]
+                MacBackend::Rusb => self.start_slam_rusb(&progress, edge, uvc_mode, embedded_algo),
+                #[cfg(not(feature = "usb"))]
+                MacBackend::Rusb => Err(XvisioError::HidCommand(
+                    "the rusb backend needs the `usb` feature".into(),
+                )),
             }
         } else {
-            self.start_slam_hidapi(edge, embedded_algo)
+            match config.backend {
+                Backend::Hidapi => self.start_slam_hidapi(&progress, edge, uvc_mode, embedded_algo),
+                #[cfg(feature = "usb")]
+                Backend::Rusb => self.start_slam_libusb(&progress, edge, uvc_mode, embedded_algo),
+                #[cfg(not(feature = "usb"))]
+                Backend::Rusb => Err(XvisioError::HidCommand(
+                    "the rusb backend needs the `usb` feature".into(),
+                )),
+            }
+        };
+        if result.is_err() && self.hid.is_none() && !self.device_path.is_empty() {
+            self.restore_handles();
         }
+        let mut stream = result?;
+        stream.record_startup(began, progress.into_phases());
+        self.mode = Some(config.mode);
+        Ok(stream)
+    }
+
+    /// Re-open the command handle after a failed start or a stream dropped it, so
+    /// the `Device` stays usable, and stop any stream left running.
+    /// Best effort: failures are only logged.
+    fn restore_handles(&mut self) {
+        log::info!("Re-opening the command handle");
+        // Not the caller's config: its cancel token may be what aborted the start.
+        let config = SlamConfig::new(SlamMode::Edge);
+        match self.reopen_hid_handle(&config, REOPEN_ATTEMPTS, REOPEN_DELAY) {
+            Ok(()) => {
+                if let Err(e) = self.reset() {
+                    log::warn!("Failed to stop edge stream after failed start: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Failed to restore the command handle: {}", e),
+        }
+    }
+
+    /// SLAM start on a device opened with [`Device::open_from_fd`] or through
+    /// [`Backend::Rusb`]: commands and the reader share the one libusb handle.
+    #[cfg(feature = "usb")]
+    fn start_slam_usb(
+        mut usb: HidTransport<rusb::DeviceHandle<rusb::GlobalContext>>,
+        progress: &StartupProgress,
+        edge: bool,
+        uvc_mode: Option<u8>,
+        embedded_algo: bool,
+    ) -> Result<SlamStream> {
+        let config = progress.config;
+        let sequence = config.startup_sequence.clone().unwrap_or_else(|| {
+            StartupSequence::standard(
+                edge,
+                uvc_mode.unwrap_or(0),
+                embedded_algo,
+                edge,
+                config.flipped,
+            )
+        });
+        run_startup(&sequence, progress, &mut usb)?;
+        progress.report(StartupPhase::Streaming);
+        SlamStream::start_rusb(usb.into_device(), config, sequence.edge_stream_command())
+    }
+
+    /// libusb-based SLAM start off macOS, see [`Backend::Rusb`]: claim this device's
+    /// HID interface with the kernel driver auto-detached, then run the shared
+    /// handle path of [`Device::open_from_fd`].
+    #[cfg(feature = "usb")]
+    fn start_slam_libusb(
+        &mut self,
+        progress: &StartupProgress,
+        edge: bool,
+        uvc_mode: Option<u8>,
+        embedded_algo: bool,
+    ) -> Result<SlamStream> {
+        let device = self.usb_device()?;
+        // Detaching the driver removes the hidraw node behind the command handle.
+        drop(self.hid.take());
+        drop(self.api.take());
+        let handle = device.open()?;
+        if let Err(e) = handle.set_auto_detach_kernel_driver(true) {
+            log::debug!("libusb auto-detach unavailable: {}", e);
+        }
+        handle
+            .claim_interface(crate::protocol::HID_INTERFACE)
+            .map_err(|source| XvisioError::InterfaceClaimFailed {
+                interface: crate::protocol::HID_INTERFACE,
+                source,
+            })?;
+        log::info!(
+            "SLAM backend: libusb on bus {} address {}",
+            device.bus_number(),
+            device.address()
+        );
+        Self::start_slam_usb(
+            HidTransport::new(handle),
+            progress,
+            edge,
+            uvc_mode,
+            embedded_algo,
+        )
     }
 
     /// hidapi-based SLAM start (Windows/Linux, optional on macOS).
-    fn start_slam_hidapi(&mut self, edge: bool, embedded_algo: bool) -> Result<SlamStream> {
+    fn start_slam_hidapi(
+        &mut self,
+        progress: &StartupProgress,
+        edge: bool,
+        uvc_mode: Option<u8>,
+        embedded_algo: bool,
+    ) -> Result<SlamStream> {
+        let config = progress.config;
         // On macOS, configure frequently causes USB re-enumeration.
         // Re-open and retry edge-start to avoid using a stale HID handle.
         if cfg!(target_os = "macos") {
-            return self.start_slam_hidapi_macos(edge, embedded_algo);
+            return self.start_slam_hidapi_macos(progress, edge, uvc_mode, embedded_algo);
         }
 
-        let hid = self
-            .hid
-            .as_ref()
-            .ok_or_else(|| XvisioError::HidCommand("Device handle already consumed".into()))?;
-
-        hid.configure(edge, embedded_algo)?;
-        std::thread::sleep(std::time::Duration::from_secs(1));
-        hid.edge_stream(edge)?;
+        let sequence = config.startup_sequence.clone().unwrap_or_else(|| {
+            StartupSequence::standard(
+                edge,
+                uvc_mode.unwrap_or(0),
+                embedded_algo,
+                edge,
+                config.flipped,
+            )
+        });
+        let mut target = HidapiStartup {
+            device: self,
+            progress,
+            attempts: 1,
+        };
+        run_startup(&sequence, progress, &mut target)?;
 
         // Open a second HID handle for the SLAM reader thread.
         let api = create_hid_api()?;
         let slam_device = api.open_path(&self.device_path)?;
-        SlamStream::start_hidapi(slam_device, api)
+        progress.report(StartupPhase::Streaming);
+        SlamStream::start_hidapi(slam_device, api, config)
     }
 
-    fn start_slam_hidapi_macos(&mut self, edge: bool, embedded_algo: bool) -> Result<SlamStream> {
-        let uvc_mode = Self::read_env_u8("XVISIO_UVC_MODE", 1);
+    fn start_slam_hidapi_macos(
+        &mut self,
+        progress: &StartupProgress,
+        edge: bool,
+        uvc_mode: Option<u8>,
+        embedded_algo: bool,
+    ) -> Result<SlamStream> {
+        let config = progress.config;
+        let uvc_mode = uvc_mode.unwrap_or_else(|| Self::read_env_u8("XVISIO_UVC_MODE", 1));
         let rotation_enabled = Self::read_env_bool("XVISIO_ROTATION_ENABLED", true);
         let enable_stereo_init = Self::read_env_bool("XVISIO_ENABLE_STEREO_INIT", false);
         let reopen_after_config = Self::read_env_bool("XVISIO_REOPEN_AFTER_CONFIG", true);
-        let reconnect_attempts = 40usize;
-        let reconnect_delay = std::time::Duration::from_millis(100);
         log::info!(
             "macOS hidapi params: uvcMode={} rotationEnabled={} stereoInit={} reopenAfterConfig={}",
             uvc_mode,
@@ -240,156 +1067,36 @@ impl Device {
             reopen_after_config
         );
 
-        {
-            let hid = self
-                .hid
-                .as_ref()
-                .ok_or_else(|| XvisioError::HidCommand("Device handle already consumed".into()))?;
-            hid.configure_with_uvc(edge, uvc_mode, embedded_algo)?;
-        }
-
-        // Same delay as the official flow after configure.
-        std::thread::sleep(std::time::Duration::from_secs(1));
-
-        if reopen_after_config {
-            self.reopen_hid_handle(reconnect_attempts, reconnect_delay)?;
-        }
-
+        let mut sequence = StartupSequence::standard(
+            edge,
+            uvc_mode,
+            embedded_algo,
+            rotation_enabled,
+            config.flipped,
+        );
+        sequence.steps[0].reopen_after = reopen_after_config;
         if enable_stereo_init {
-            let mut last_err: Option<XvisioError> = None;
-            let mut stereo_init_ok = false;
-            for attempt in 1..=reconnect_attempts {
-                let res = {
-                    let hid = self.hid.as_ref().ok_or_else(|| {
-                        XvisioError::HidCommand("Device handle already consumed".into())
-                    })?;
-                    hid.stereo_camera_init()
-                };
-                match res {
-                    Ok(_) => {
-                        stereo_init_ok = true;
-                        break;
-                    }
-                    Err(e) => {
-                        let msg = e.to_string().to_ascii_lowercase();
-                        last_err = Some(e);
-                        if msg.contains("disconnected")
-                            || msg.contains("no such device")
-                            || msg.contains("not found")
-                        {
-                            log::warn!(
-                                "hidapi stereo init retry {}/{} after reconnect",
-                                attempt,
-                                reconnect_attempts
-                            );
-                            std::thread::sleep(reconnect_delay);
-                            self.reopen_hid_handle(reconnect_attempts, reconnect_delay)?;
-                            continue;
-                        }
-                        return Err(last_err.unwrap());
-                    }
-                }
-            }
-            if !stereo_init_ok {
-                return Err(last_err.unwrap_or_else(|| {
-                    XvisioError::HidCommand("hidapi stereo init failed after retries".into())
-                }));
-            }
-
-            std::thread::sleep(std::time::Duration::from_millis(50));
-
-            let mut last_err: Option<XvisioError> = None;
-            let mut stereo_start_ok = false;
-            for attempt in 1..=reconnect_attempts {
-                let res = {
-                    let hid = self.hid.as_ref().ok_or_else(|| {
-                        XvisioError::HidCommand("Device handle already consumed".into())
-                    })?;
-                    hid.stereo_camera_start()
-                };
-                match res {
-                    Ok(_) => {
-                        stereo_start_ok = true;
-                        break;
-                    }
-                    Err(e) => {
-                        let msg = e.to_string().to_ascii_lowercase();
-                        last_err = Some(e);
-                        if msg.contains("disconnected")
-                            || msg.contains("no such device")
-                            || msg.contains("not found")
-                        {
-                            log::warn!(
-                                "hidapi stereo start retry {}/{} after reconnect",
-                                attempt,
-                                reconnect_attempts
-                            );
-                            std::thread::sleep(reconnect_delay);
-                            self.reopen_hid_handle(reconnect_attempts, reconnect_delay)?;
-                            continue;
-                        }
-                        return Err(last_err.unwrap());
-                    }
-                }
-            }
-            if !stereo_start_ok {
-                return Err(last_err.unwrap_or_else(|| {
-                    XvisioError::HidCommand("hidapi stereo start failed after retries".into())
-                }));
-            }
-            std::thread::sleep(std::time::Duration::from_millis(300));
-        }
-
-        // Edge start can still race re-enumeration; retry with reopen on disconnect.
-        let mut last_err: Option<XvisioError> = None;
-        for attempt in 1..=reconnect_attempts {
-            let edge_result = {
-                let hid = self.hid.as_ref().ok_or_else(|| {
-                    XvisioError::HidCommand("Device handle already consumed".into())
-                })?;
-                hid.edge_stream_with_params(if edge { 1 } else { 0 }, rotation_enabled, false)
-            };
-
-            match edge_result {
-                Ok(_) => {
-                    log::info!(
-                        "hidapi edge stream start succeeded (attempt {}, edgeMode={})",
-                        attempt,
-                        if edge { 1 } else { 0 }
-                    );
-                    let hid = self.hid.take().ok_or_else(|| {
-                        XvisioError::HidCommand("Device handle already consumed".into())
-                    })?;
-                    let api = self
-                        .api
-                        .take()
-                        .ok_or_else(|| XvisioError::HidCommand("HidApi context consumed".into()))?;
-                    return SlamStream::start_hidapi(hid.into_device(), api);
-                }
-                Err(e) => {
-                    let msg = e.to_string().to_ascii_lowercase();
-                    last_err = Some(e);
-                    if msg.contains("disconnected")
-                        || msg.contains("no such device")
-                        || msg.contains("not found")
-                    {
-                        log::warn!(
-                            "hidapi edge stream start retry {}/{} after reconnect",
-                            attempt,
-                            reconnect_attempts
-                        );
-                        std::thread::sleep(reconnect_delay);
-                        self.reopen_hid_handle(reconnect_attempts, reconnect_delay)?;
-                        continue;
-                    }
-                    return Err(last_err.unwrap());
-                }
-            }
+            sequence = sequence.with_stereo_init(true);
         }
+        let sequence = config.startup_sequence.clone().unwrap_or(sequence);
+        // Commands can still race re-enumeration; each is retried after a re-open.
+        let mut target = HidapiStartup {
+            device: self,
+            progress,
+            attempts: REOPEN_ATTEMPTS,
+        };
+        run_startup(&sequence, progress, &mut target)?;
 
-        Err(last_err.unwrap_or_else(|| {
-            XvisioError::HidCommand("hidapi edge stream start failed after retries".into())
-        }))
+        let hid = self
+            .hid
+            .take()
+            .ok_or_else(|| XvisioError::HidCommand("Device handle already consumed".into()))?;
+        let api = self
+            .api
+            .take()
+            .ok_or_else(|| XvisioError::HidCommand("HidApi context consumed".into()))?;
+        progress.report(StartupPhase::Streaming);
+        SlamStream::start_hidapi(hid.into_device(), api, config)
     }
 
     /// rusb-based SLAM start (macOS).
@@ -405,7 +1112,15 @@ impl Device {
     /// Extra recovery knobs are available via env vars for unstable setups:
     /// - `XVISIO_PRECONDITION_CYCLES`
     /// - `XVISIO_ENABLE_STEREO_INIT`
-    fn start_slam_rusb(&mut self, edge: bool, embedded_algo: bool) -> Result<SlamStream> {
+    #[cfg(feature = "usb")]
+    fn start_slam_rusb(
+        &mut self,
+        progress: &StartupProgress,
+        edge: bool,
+        uvc_mode: Option<u8>,
+        embedded_algo: bool,
+    ) -> Result<SlamStream> {
+        let config = progress.config;
         use crate::protocol;
 
         // Close hidapi handle first — it holds exclusive IOKit access
@@ -416,7 +1131,7 @@ impl Device {
         // Keep macOS defaults aligned with the known-good Windows/Linux path:
         // configure(edge=1, uvcMode=0, embeddedAlgo=0), then edge stream
         // with rotationEnabled=true.
-        let uvc_mode = Self::read_env_u8("XVISIO_UVC_MODE", 0);
+        let uvc_mode = uvc_mode.unwrap_or_else(|| Self::read_env_u8("XVISIO_UVC_MODE", 0));
         let rotation_enabled = Self::read_env_bool("XVISIO_ROTATION_ENABLED", true);
         let claim_all_interfaces = Self::read_env_bool("XVISIO_CLAIM_ALL_INTERFACES", false);
         let precondition_cycles = Self::read_env_u8("XVISIO_PRECONDITION_CYCLES", 0) as usize;
@@ -442,12 +1157,13 @@ impl Device {
         // with the configure command's firmware re-enum, leaves the device in a state
         // where kernel drivers haven't yet reclaimed interfaces.
         for cycle in 1..=precondition_cycles {
+            config.check_cancelled()?;
             log::info!(
                 "Precondition cycle {}/{}: detach → claim → configure → edge → release",
                 cycle,
                 precondition_cycles
             );
-            match Self::open_rusb_handle_with_detach() {
+            match Self::open_rusb_handle_with_detach(config) {
                 Ok(handle) => {
                     // Send configure
                     let cmd = protocol::build_configure_cmd_with_uvc(edge, uvc_mode, embedded_algo);
@@ -464,7 +1180,7 @@ impl Device {
                     let cmd = protocol::build_edge_stream_cmd_with_params(
                         if edge { 1 } else { 0 },
                         rotation_enabled,
-                        false,
+                        config.flipped,
                     );
                     let _ = Self::send_hid_command_rusb(
                         &handle,
@@ -476,7 +1192,7 @@ impl Device {
                     std::thread::sleep(std::time::Duration::from_millis(200));
 
                     // Release — handle drops, device re-enumerates
-                    let _ = handle.release_interface(protocol::HID_INTERFACE as u8);
+                    let _ = handle.release_interface(protocol::HID_INTERFACE);
                 }
                 Err(e) => {
                     log::warn!("Precondition cycle {} failed: {} (continuing)", cycle, e);
@@ -494,97 +1210,56 @@ impl Device {
         // The preconditioning cycles have cleared kernel drivers, so claim should work
         // in the tight window before they re-bind.
         log::info!("Main sequence: claim interfaces without detach...");
-        let mut handle =
-            Self::open_rusb_handle_no_detach(claim_all_interfaces, allow_detach_fallback)?;
+        let handle =
+            Self::open_rusb_handle_no_detach(config, claim_all_interfaces, allow_detach_fallback)?;
 
-        // 1. Configure
-        log::info!("Sending configure command...");
-        let cmd = protocol::build_configure_cmd_with_uvc(edge, uvc_mode, embedded_algo);
-        Self::send_hid_command_rusb(&handle, &cmd, protocol::CMD_CONFIGURE, timeout, "configure")?;
-        std::thread::sleep(std::time::Duration::from_secs(1));
-
-        // On some macOS setups, configure triggers a USB re-enumeration and invalidates
-        // the current handle. Re-open proactively before sending follow-up commands.
-        if reopen_after_config {
-            drop(handle);
-            std::thread::sleep(std::time::Duration::from_millis(200));
-            log::info!("Re-opening handle after configure...");
-            handle = Self::open_rusb_handle_no_detach(claim_all_interfaces, allow_detach_fallback)?;
-        }
-
-        if enable_stereo_init {
-            // 2. Stereo camera init (required on some macOS setups for non-identity poses)
-            log::info!("Sending stereo camera init...");
-            let cmd = protocol::build_stereo_camera_init_cmd();
-            match Self::send_hid_command_rusb(
-                &handle,
-                &cmd,
-                protocol::CMD_STEREO_CAMERA_INIT,
-                timeout,
-                "stereo camera init",
-            ) {
-                Ok(_) => log::info!("Stereo camera init sent"),
-                Err(e) => log::warn!("Stereo camera init failed: {} (continuing)", e),
-            }
-            std::thread::sleep(std::time::Duration::from_millis(50));
-
-            // 3. Stereo camera start
-            log::info!("Sending stereo camera start...");
-            let cmd = protocol::build_stereo_camera_start_cmd();
-            match Self::send_hid_command_rusb(
-                &handle,
-                &cmd,
-                protocol::CMD_STEREO_CAMERA_START,
-                timeout,
-                "stereo camera start",
-            ) {
-                Ok(_) => log::info!("Stereo camera start sent"),
-                Err(e) => log::warn!("Stereo camera start failed: {} (continuing)", e),
-            }
-            std::thread::sleep(std::time::Duration::from_millis(300));
-        } else {
-            log::info!("Skipping stereo camera init/start (XVISIO_ENABLE_STEREO_INIT=0)");
-        }
-
-        // 4. Edge stream start
-        log::info!("Sending edge stream start...");
-        let cmd = protocol::build_edge_stream_cmd_with_params(
-            if edge { 1 } else { 0 },
+        let mut sequence = StartupSequence::standard(
+            edge,
+            uvc_mode,
+            embedded_algo,
             rotation_enabled,
-            false,
+            config.flipped,
         );
-        Self::send_hid_command_rusb(
-            &handle,
-            &cmd,
-            protocol::CMD_EDGE_STREAM,
-            timeout,
-            "edge stream start",
-        )?;
-        log::info!("Edge stream start sent");
-
-        std::thread::sleep(std::time::Duration::from_millis(300));
-
-        // Some macOS runs re-enumerate again right after edge stream enable.
-        // Optional re-open avoids starting the reader on a stale handle.
-        if reopen_after_edge_start {
-            drop(handle);
-            std::thread::sleep(std::time::Duration::from_millis(200));
-            log::info!("Re-opening handle after edge stream start...");
-            handle = Self::open_rusb_handle_no_detach(claim_all_interfaces, allow_detach_fallback)?;
+        if enable_stereo_init {
+            // Required on some macOS setups for non-identity poses.
+            sequence = sequence.with_stereo_init(false);
+        }
+        // On some macOS setups, configure triggers a USB re-enumeration and invalidates
+        // the current handle; some runs re-enumerate again right after edge stream
+        // enable. Re-opening avoids sending to, or reading from, a stale handle.
+        sequence.steps[0].reopen_after = reopen_after_config;
+        if let Some(edge_start) = sequence.steps.last_mut() {
+            edge_start.delay_after = std::time::Duration::from_millis(300);
+            edge_start.reopen_after = reopen_after_edge_start;
         }
+        let sequence = config.startup_sequence.clone().unwrap_or(sequence);
+        let mut target = RusbStartup {
+            config,
+            handle: Some(handle),
+            claim_all_interfaces,
+            allow_detach_fallback,
+            timeout,
+        };
+        run_startup(&sequence, progress, &mut target)?;
+        let handle = target.handle.ok_or(XvisioError::DeviceNotFound)?;
 
         // Start SLAM reading on the same handle
-        SlamStream::start_rusb(handle)
+        progress.report(StartupPhase::Streaming);
+        SlamStream::start_rusb(handle, config, sequence.edge_stream_command())
     }
 
     /// Open XR50 via rusb WITH kernel driver detach. Used for preconditioning cycles.
     /// Retries up to 10 times to handle USB re-enumeration delays.
-    fn open_rusb_handle_with_detach() -> Result<rusb::DeviceHandle<rusb::GlobalContext>> {
+    #[cfg(feature = "usb")]
+    fn open_rusb_handle_with_detach(
+        config: &SlamConfig,
+    ) -> Result<rusb::DeviceHandle<rusb::GlobalContext>> {
         use crate::protocol;
 
+        let mut last_err = XvisioError::DeviceNotFound;
         for attempt in 1..=10 {
-            let devices = rusb::devices()
-                .map_err(|e| XvisioError::HidCommand(format!("rusb enumerate: {}", e)))?;
+            config.check_cancelled()?;
+            let devices = rusb::devices()?;
 
             let usb_device = match devices.iter().find(|d| {
                 d.device_descriptor()
@@ -603,20 +1278,21 @@ impl Device {
                 Ok(h) => h,
                 Err(e) => {
                     log::warn!("rusb open failed (attempt {}): {}", attempt, e);
+                    last_err = e.into();
                     std::thread::sleep(std::time::Duration::from_millis(500));
                     continue;
                 }
             };
 
             // Detach kernel driver (device-wide on macOS)
-            match handle.detach_kernel_driver(protocol::HID_INTERFACE as u8) {
+            match handle.detach_kernel_driver(protocol::HID_INTERFACE) {
                 Ok(_) => log::info!("Detached kernel driver"),
                 Err(rusb::Error::NotFound) => {}
                 Err(rusb::Error::NotSupported) => {}
                 Err(e) => log::warn!("Detach: {} (continuing)", e),
             }
 
-            match handle.claim_interface(protocol::HID_INTERFACE as u8) {
+            match handle.claim_interface(protocol::HID_INTERFACE) {
                 Ok(_) => {
                     log::info!(
                         "Claimed interface {} (attempt {})",
@@ -627,15 +1303,18 @@ impl Device {
                 }
                 Err(e) => {
                     log::warn!("Claim failed: {} (attempt {})", e, attempt);
+                    last_err = XvisioError::InterfaceClaimFailed {
+                        interface: protocol::HID_INTERFACE,
+                        source: e,
+                    };
                     std::thread::sleep(std::time::Duration::from_millis(500));
                     continue;
                 }
             }
         }
 
-        Err(XvisioError::HidCommand(
-            "Failed to open XR50 via rusb after 10 attempts (try sudo)".into(),
-        ))
+        log::error!("Failed to open XR50 via rusb after 10 attempts (try sudo)");
+        Err(last_err)
     }
 
     /// Open XR50 via rusb WITHOUT kernel driver detach. Used for the main SLAM sequence
@@ -643,7 +1322,9 @@ impl Device {
     /// By default claims all interfaces [3,1,2,0], which is more robust on macOS.
     /// Set `XVISIO_CLAIM_ALL_INTERFACES=0` to prefer interface 3 first.
     /// Retries up to 20 times with short intervals.
+    #[cfg(feature = "usb")]
     fn open_rusb_handle_no_detach(
+        config: &SlamConfig,
         claim_all_interfaces: bool,
         allow_detach_fallback: bool,
     ) -> Result<rusb::DeviceHandle<rusb::GlobalContext>> {
@@ -657,9 +1338,10 @@ impl Device {
             &[IFACES_HID, IFACES_ALL]
         };
 
+        let mut last_err = XvisioError::DeviceNotFound;
         for attempt in 1..=20 {
-            let devices = rusb::devices()
-                .map_err(|e| XvisioError::HidCommand(format!("rusb enumerate: {}", e)))?;
+            config.check_cancelled()?;
+            let devices = rusb::devices()?;
 
             let usb_device = match devices.iter().find(|d| {
                 d.device_descriptor()
@@ -679,6 +1361,7 @@ impl Device {
                     Ok(h) => h,
                     Err(e) => {
                         log::warn!("rusb open failed (attempt {}): {}", attempt, e);
+                        last_err = e.into();
                         std::thread::sleep(std::time::Duration::from_millis(300));
                         continue;
                     }
@@ -721,6 +1404,10 @@ impl Device {
                                         e,
                                         attempt
                                     );
+                                    last_err = XvisioError::InterfaceClaimFailed {
+                                        interface: iface,
+                                        source: e,
+                                    };
                                     all_claimed = false;
                                     break;
                                 }
@@ -733,6 +1420,10 @@ impl Device {
                                 e,
                                 attempt
                             );
+                            last_err = XvisioError::InterfaceClaimFailed {
+                                interface: iface,
+                                source: e,
+                            };
                             all_claimed = false;
                             break;
                         }
@@ -752,12 +1443,12 @@ impl Device {
             std::thread::sleep(std::time::Duration::from_millis(300));
         }
 
-        Err(XvisioError::HidCommand(
-            "Failed to claim XR50 interfaces without detach after 20 attempts".into(),
-        ))
+        log::error!("Failed to claim XR50 interfaces without detach after 20 attempts");
+        Err(last_err)
     }
 
-    fn send_hid_command_rusb(
+    #[cfg(feature = "usb")]
+    pub(crate) fn send_hid_command_rusb(
         handle: &rusb::DeviceHandle<rusb::GlobalContext>,
         cmd: &[u8; crate::protocol::REPORT_SIZE],
         expected_echo: &[u8],
@@ -775,7 +1466,10 @@ impl Device {
                 cmd,
                 timeout,
             )
-            .map_err(|e| XvisioError::HidCommand(format!("{} write failed: {}", label, e)))?;
+            .map_err(|e| {
+                log::warn!("{} write failed: {}", label, e);
+                XvisioError::from(e)
+            })?;
 
         let mut response = [0u8; protocol::REPORT_SIZE];
         match handle.read_control(
@@ -787,16 +1481,18 @@ impl Device {
             timeout,
         ) {
             Ok(len) => {
-                if len < 1 + expected_echo.len() {
-                    log::warn!("{} ack too short ({} bytes)", label, len);
-                } else if response[0] != protocol::PREFIX_DEVICE_TO_HOST
-                    || &response[1..1 + expected_echo.len()] != expected_echo
-                {
+                let ack = protocol::Response::parse(&response[..len], expected_echo);
+                if ack.is_rejection() {
+                    log::warn!("{} rejected: echo={:02x?}", label, ack.echo);
+                    return Err(XvisioError::CommandRejected(expected_echo.to_vec()));
+                } else if ack.is_slam_packet() {
+                    log::warn!("{} answered by a SLAM packet: still streaming", label);
+                } else if !ack.valid {
                     log::warn!(
-                        "{} ack mismatch: prefix=0x{:02x} echo={:02x?}",
+                        "{} no ack: prefix=0x{:02x} ({} bytes)",
                         label,
-                        response[0],
-                        &response[1..1 + expected_echo.len()],
+                        ack.prefix,
+                        len
                     );
                 }
             }
@@ -808,13 +1504,19 @@ impl Device {
         Ok(())
     }
 
-    fn reopen_hid_handle(&mut self, attempts: usize, delay: std::time::Duration) -> Result<()> {
+    fn reopen_hid_handle(
+        &mut self,
+        config: &SlamConfig,
+        attempts: usize,
+        delay: std::time::Duration,
+    ) -> Result<()> {
         drop(self.hid.take());
         drop(self.api.take());
 
         for attempt in 1..=attempts {
+            config.check_cancelled()?;
             let api = create_hid_api()?;
-            let hid_info = match api.device_list().find(|d| is_xr50_hid(d)) {
+            let hid_info = match find_xr50_hid(&api) {
                 Some(d) => d,
                 None => {
                     if attempt <= 5 || attempt % 10 == 0 {
@@ -880,3 +1582,255 @@ impl Device {
             .unwrap_or_else(|| default.to_string())
     }
 }
+
+/// Handle a [`StartupSequence`] is sent over.
+trait StartupTarget {
+    fn send(&mut self, command: &CommandBuilder) -> Result<()>;
+    /// Replace the handle after the device re-enumerated.
+    fn reopen(&mut self) -> Result<()>;
+}
+
+/// Send `sequence` over `target` with the pauses and re-opens it asks for.
+fn run_startup(
+    sequence: &StartupSequence,
+    progress: &StartupProgress,
+    target: &mut impl StartupTarget,
+) -> Result<()> {
+    for step in &sequence.steps {
+        progress.config.check_cancelled()?;
+        progress.report(step.phase);
+        log::info!("Sending {:02x?}...", step.command.bytes());
+        match target.send(&step.command) {
+            Ok(()) => {}
+            Err(e) if !step.required => log::warn!("{:?} failed: {} (continuing)", step.phase, e),
+            Err(e) => return Err(e),
+        }
+        std::thread::sleep(step.delay_after);
+        if step.reopen_after {
+            progress.report(StartupPhase::Reopening);
+            target.reopen()?;
+        }
+    }
+    Ok(())
+}
+
+/// A handle that stays the same for the whole start, e.g. one from
+/// [`Device::open_from_fd`].
+impl<T: Transport> StartupTarget for HidTransport<T> {
+    fn send(&mut self, command: &CommandBuilder) -> Result<()> {
+        self.send_acked(command)
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        Err(XvisioError::HidCommand(
+            "this handle can't be re-opened during startup".into(),
+        ))
+    }
+}
+
+/// The hidapi command handle of a [`Device`]. A command failing because the
+/// device dropped off the bus is retried after a re-open, up to `attempts` tries.
+struct HidapiStartup<'a> {
+    device: &'a mut Device,
+    progress: &'a StartupProgress<'a>,
+    attempts: usize,
+}
+
+impl StartupTarget for HidapiStartup<'_> {
+    fn send(&mut self, command: &CommandBuilder) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.progress.config.check_cancelled()?;
+            match self.device.hid()?.send_acked(command) {
+                Err(e) if e.is_disconnect() && attempt < self.attempts => {
+                    log::warn!(
+                        "hidapi {:02x?} retry {}/{} after reconnect",
+                        command.echo(),
+                        attempt,
+                        self.attempts
+                    );
+                    self.progress.report(StartupPhase::Retrying {
+                        attempt,
+                        max_attempts: self.attempts,
+                    });
+                    std::thread::sleep(REOPEN_DELAY);
+                    self.reopen()?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        self.device
+            .reopen_hid_handle(self.progress.config, REOPEN_ATTEMPTS, REOPEN_DELAY)
+    }
+}
+
+/// The claimed libusb handle of the macOS rusb backend.
+#[cfg(feature = "usb")]
+struct RusbStartup<'a> {
+    config: &'a SlamConfig,
+    /// `None` only after a failed re-open.
+    handle: Option<rusb::DeviceHandle<rusb::GlobalContext>>,
+    claim_all_interfaces: bool,
+    allow_detach_fallback: bool,
+    timeout: Duration,
+}
+
+#[cfg(feature = "usb")]
+impl StartupTarget for RusbStartup<'_> {
+    fn send(&mut self, command: &CommandBuilder) -> Result<()> {
+        let handle = self.handle.as_ref().ok_or(XvisioError::DeviceNotFound)?;
+        let label = format!("command {:02x?}", command.echo());
+        Device::send_hid_command_rusb(
+            handle,
+            &command.build(),
+            command.echo(),
+            self.timeout,
+            &label,
+        )
+    }
+
+    fn reopen(&mut self) -> Result<()> {
+        drop(self.handle.take());
+        std::thread::sleep(Duration::from_millis(200));
+        log::info!("Re-opening handle...");
+        self.handle = Some(Device::open_rusb_handle_no_detach(
+            self.config,
+            self.claim_all_interfaces,
+            self.allow_detach_fallback,
+        )?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hid::mock::MockTransport;
+    use crate::protocol;
+
+    fn blank_info() -> DeviceInfo {
+        DeviceInfo {
+            uuid: String::new(),
+            version: String::new(),
+            features: Features::empty(),
+            bus_id: "mock".to_string(),
+            bus_number: 0,
+            device_address: 0,
+            usage_page: HID_USAGE_PAGE_VENDOR,
+            usage: 0,
+            serial_number: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_run_startup_sequence() {
+        let mut sequence =
+            StartupSequence::standard(true, 0, false, true, false).with_stereo_init(false);
+        let echoes: Vec<&[u8]> = sequence.steps.iter().map(|s| s.command.echo()).collect();
+        assert_eq!(
+            echoes,
+            [
+                protocol::CMD_CONFIGURE,
+                protocol::CMD_STEREO_CAMERA_INIT,
+                protocol::CMD_STEREO_CAMERA_START,
+                protocol::CMD_EDGE_STREAM,
+            ]
+        );
+        #[cfg(feature = "usb")]
+        assert_eq!(
+            sequence.edge_stream_command(),
+            protocol::build_edge_stream_cmd(true)
+        );
+
+        for step in &mut sequence.steps {
+            step.delay_after = Duration::ZERO;
+        }
+        let config = SlamConfig::new(SlamMode::Edge);
+        let progress = StartupProgress::new(&config);
+        let mut hid = HidTransport::new(MockTransport::default());
+        run_startup(&sequence, &progress, &mut hid).unwrap();
+        let phases: Vec<_> = progress.into_phases().into_iter().map(|(p, _)| p).collect();
+        assert_eq!(
+            phases,
+            [
+                StartupPhase::Configuring,
+                StartupPhase::StereoInit,
+                StartupPhase::StereoInit,
+                StartupPhase::EdgeStreamStart,
+            ]
+        );
+
+        // A handle from a file descriptor can't follow a re-enumeration.
+        sequence.steps[0].reopen_after = true;
+        assert!(run_startup(&sequence, &StartupProgress::new(&config), &mut hid).is_err());
+    }
+
+    #[test]
+    fn test_read_identity() {
+        let hid = HidTransport::new(
+            MockTransport::default()
+                .respond(protocol::CMD_UUID, b"XR50-1234\0")
+                .respond(protocol::CMD_VERSION, b"1V1.04P31||xr50|V1.09\0")
+                .respond(protocol::CMD_FEATURES, &[0x05, 0x00, 0x00, 0x00]),
+        );
+        let info = read_identity(&hid, blank_info()).unwrap();
+        assert_eq!(info.uuid, "XR50-1234");
+        assert_eq!(info.version, "1V1.04P31||xr50|V1.09");
+        assert_eq!(info.features, Features::EDGE_MODE | Features::STEREO);
+        assert_eq!(info.bus_id, "mock");
+    }
+
+    #[test]
+    fn test_read_identity_rejects_unknown_device() {
+        let hid = HidTransport::new(
+            MockTransport::default()
+                .respond(protocol::CMD_UUID, b"DS80-1234\0")
+                .respond(protocol::CMD_VERSION, b"1V2.00||ds80|V2.00\0"),
+        );
+        assert!(matches!(
+            read_identity(&hid, blank_info()),
+            Err(XvisioError::UnsupportedDevice { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_supported_needs_a_different_product() {
+        assert!(check_supported("XR50-1234", "1V1.04P31||XR50|V1.09").is_ok());
+        assert!(check_supported("XR50-1234", "1V1.04P31").is_ok());
+        assert!(check_supported("XR50-1234", "1V1.04P31|||V1.09").is_ok());
+        assert!(check_supported("DS80-1234", "1V2.00||ds80|V2.00").is_err());
+    }
+
+    #[test]
+    fn test_read_identity_stops_stale_stream() {
+        let hid = HidTransport::new(
+            MockTransport::default()
+                .streaming()
+                .respond(protocol::CMD_UUID, b"XR50-1234\0")
+                .respond(protocol::CMD_VERSION, b"1V1.04P31||xr50|V1.09\0")
+                .respond(protocol::CMD_FEATURES, &[0x01, 0x00, 0x00, 0x00]),
+        );
+        assert!(matches!(hid.read_uuid(), Err(XvisioError::StillStreaming)));
+        let (uuid, version, _) = Device::read_device_identity(&hid).unwrap();
+        assert_eq!(uuid, "XR50-1234");
+        assert_eq!(version, "1V1.04P31||xr50|V1.09");
+    }
+
+    #[test]
+    fn test_libusb_path_location() {
+        assert_eq!(libusb_path_location("0001:000a:03"), Some((1, 10)));
+        assert_eq!(libusb_path_location("/dev/hidraw3"), None);
+        assert_eq!(libusb_path_location("DevSrvsID:4294969674"), None);
+    }
+
+    #[test]
+    fn test_read_identity_missing_response() {
+        let hid = HidTransport::new(MockTransport::default().respond(protocol::CMD_UUID, b"X\0"));
+        assert!(read_identity(&hid, blank_info()).is_err());
+    }
+}
@@ -1,11 +1,33 @@
-use std::fmt;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 /// Errors that can occur when interacting with the XR50 device.
 #[derive(Debug, thiserror::Error)]
 pub enum XvisioError {
+    #[cfg(feature = "std")]
     #[error("HID error: {0}")]
     Hid(#[from] hidapi::HidError),
 
+    #[cfg(feature = "std")]
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "usb")]
+    #[error("USB access denied (try sudo or a udev rule): {0}")]
+    UsbAccess(rusb::Error),
+
+    #[cfg(feature = "usb")]
+    #[error("USB device busy: {0}")]
+    UsbBusy(rusb::Error),
+
+    #[cfg(feature = "usb")]
+    #[error("Failed to claim USB interface {interface}: {source}")]
+    InterfaceClaimFailed { interface: u8, source: rusb::Error },
+
+    #[cfg(feature = "usb")]
+    #[error("USB error: {0}")]
+    Usb(rusb::Error),
+
     #[error("Device not found (VID=040E PID=F408)")]
     DeviceNotFound,
 
@@ -15,41 +37,118 @@ pub enum XvisioError {
     #[error("Invalid response: expected prefix 0x01, got 0x{0:02x}")]
     InvalidResponse(u8),
 
+    #[error("Invalid {field} in response: {raw:02x?}")]
+    InvalidString { field: &'static str, raw: Vec<u8> },
+
+    #[error("Device answered a query with SLAM data: still streaming from a previous session")]
+    StillStreaming,
+
+    #[error("Unsupported device: UUID {uuid:?}, version {version:?} don't look like an XR50")]
+    UnsupportedDevice { uuid: String, version: String },
+
+    #[error("Command too long: {0} bytes, a report holds at most 62")]
+    CommandTooLong(usize),
+
     #[error("Command echo mismatch")]
     CommandMismatch,
 
+    #[error("Device rejected command {0:02x?}")]
+    CommandRejected(Vec<u8>),
+
     #[error("SLAM stream stopped")]
     StreamStopped,
 
+    #[error("SLAM stream stalled: device timestamp stopped advancing")]
+    StreamStalled,
+
+    #[error(
+        "No tracking sample within {0:?} of starting SLAM \
+         (uvcvideo bound on Linux, or stereo init needed on macOS?)"
+    )]
+    FirstSampleTimeout(core::time::Duration),
+
+    #[error("IMU calibration failed: the accelerometer read zero")]
+    AccelerometerZero,
+
     #[error("Timeout waiting for data")]
     Timeout,
 
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    #[error("Not supported by the XR50 firmware: {0}")]
+    Unsupported(&'static str),
+
     #[error("Channel disconnected")]
     ChannelDisconnected,
 }
 
-/// Thread-safe last-error storage for the C FFI layer.
+#[cfg(feature = "usb")]
+impl From<rusb::Error> for XvisioError {
+    fn from(e: rusb::Error) -> Self {
+        match e {
+            rusb::Error::Access => Self::UsbAccess(e),
+            rusb::Error::Busy => Self::UsbBusy(e),
+            _ => Self::Usb(e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl XvisioError {
+    /// Whether the device dropped off the bus (e.g. during USB re-enumeration),
+    /// so re-opening and retrying may succeed.
+    pub fn is_disconnect(&self) -> bool {
+        // hidapi only reports platform message strings, so those still need matching.
+        let hid_message = |msg: &str| {
+            let msg = msg.to_ascii_lowercase();
+            msg.contains("disconnected")
+                || msg.contains("no such device")
+                || msg.contains("not found")
+        };
+        match self {
+            Self::DeviceNotFound => true,
+            #[cfg(feature = "usb")]
+            Self::Usb(rusb::Error::NoDevice) => true,
+            #[cfg(feature = "usb")]
+            Self::InterfaceClaimFailed { source, .. } => *source == rusb::Error::NoDevice,
+            Self::Hid(e) => hid_message(&e.to_string()),
+            Self::HidCommand(msg) => hid_message(msg),
+            _ => false,
+        }
+    }
+}
+
+/// Last error message and code of one thread, for the C FFI layer.
+#[cfg(feature = "std")]
 pub(crate) struct LastError {
-    message: std::sync::Mutex<String>,
+    message: String,
+    code: i32,
 }
 
+#[cfg(feature = "std")]
 impl LastError {
     pub const fn new() -> Self {
         Self {
-            message: std::sync::Mutex::new(String::new()),
+            message: String::new(),
+            code: crate::ffi::XV_OK,
         }
     }
 
-    pub fn set(&self, err: &XvisioError) {
-        if let Ok(mut msg) = self.message.lock() {
-            *msg = fmt::format(format_args!("{}\0", err));
-        }
+    pub fn set(&mut self, err: &XvisioError) {
+        self.message = format!("{}\0", err);
+        self.code = crate::ffi::error_code(err);
+    }
+
+    pub fn code(&self) -> i32 {
+        self.code
     }
 
     pub fn as_ptr(&self) -> *const std::ffi::c_char {
-        match self.message.lock() {
-            Ok(msg) if !msg.is_empty() => msg.as_ptr() as *const std::ffi::c_char,
-            _ => std::ptr::null(),
+        if self.message.is_empty() {
+            std::ptr::null()
+        } else {
+            self.message.as_ptr() as *const std::ffi::c_char
         }
     }
 }
@@ -0,0 +1,104 @@
+//! Trajectory export in the formats of common SLAM evaluation tools.
+//!
+//! Both writers stamp poses with the device clock, unwrapped across its 2^32 µs
+//! (~71.6 min) rollover, and write the quaternion from `pose.quaternion`.
+
+use crate::types::SlamSample;
+use crate::Result;
+use std::io::Write;
+
+/// Write `samples` as a TUM trajectory: one `timestamp tx ty tz qx qy qz qw`
+/// line per pose, timestamp in seconds.
+pub fn write_tum<W: Write>(samples: &[SlamSample], w: &mut W) -> Result<()> {
+    for (device_us, sample) in device_times(samples) {
+        let [x, y, z] = sample.pose.translation;
+        let [qx, qy, qz, qw] = sample.pose.quaternion;
+        writeln!(
+            w,
+            "{}.{:06} {} {} {} {} {} {} {}",
+            device_us / 1_000_000,
+            device_us % 1_000_000,
+            x,
+            y,
+            z,
+            qx,
+            qy,
+            qz,
+            qw
+        )?;
+    }
+    Ok(())
+}
+
+/// Write `samples` as an EuRoC ground-truth CSV: a header, then
+/// `timestamp [ns], p x, p y, p z, q w, q x, q y, q z` per pose.
+pub fn write_euroc<W: Write>(samples: &[SlamSample], w: &mut W) -> Result<()> {
+    writeln!(
+        w,
+        "#timestamp [ns],p_RS_R_x [m],p_RS_R_y [m],p_RS_R_z [m],q_RS_w [],q_RS_x [],q_RS_y [],q_RS_z []"
+    )?;
+    for (device_us, sample) in device_times(samples) {
+        let [x, y, z] = sample.pose.translation;
+        let [qx, qy, qz, qw] = sample.pose.quaternion;
+        writeln!(
+            w,
+            "{},{},{},{},{},{},{},{}",
+            device_us * 1000,
+            x,
+            y,
+            z,
+            qw,
+            qx,
+            qy,
+            qz
+        )?;
+    }
+    Ok(())
+}
+
+/// Pair each sample with its device time in µs, continuing past clock wraps.
+fn device_times(samples: &[SlamSample]) -> impl Iterator<Item = (u64, &SlamSample)> {
+    let mut last: Option<(u32, u64)> = None;
+    samples.iter().map(move |sample| {
+        let raw = sample.pose.timestamp_us as u32;
+        let unwrapped = match last {
+            Some((prev, time)) => time + raw.wrapping_sub(prev) as u64,
+            None => raw as u64,
+        };
+        last = Some((raw, unwrapped));
+        (unwrapped, sample)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Pose;
+
+    #[test]
+    fn test_tum_and_euroc_lines() {
+        let sample = |timestamp_us: u64| SlamSample {
+            pose: Pose {
+                timestamp_us,
+                ..Pose::new([1.0, 2.0, -0.5], [0.0, 0.0, 0.0, 1.0])
+            },
+            imu: None,
+            raw_extended: [0; 26],
+            sequence: 0,
+            host_elapsed: None,
+        };
+        let samples = [sample(u32::MAX as u64 - 499), sample(500)];
+
+        let mut tum = Vec::new();
+        write_tum(&samples, &mut tum).unwrap();
+        assert_eq!(
+            String::from_utf8(tum).unwrap(),
+            "4294.966796 1 2 -0.5 0 0 0 1\n4294.967796 1 2 -0.5 0 0 0 1\n"
+        );
+
+        let mut euroc = Vec::new();
+        write_euroc(&samples, &mut euroc).unwrap();
+        let euroc = String::from_utf8(euroc).unwrap();
+        assert_eq!(euroc.lines().nth(2), Some("4294967796000,1,2,-0.5,1,0,0,0"));
+    }
+}
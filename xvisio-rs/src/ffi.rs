@@ -6,15 +6,86 @@
 use crate::device::Device;
 use crate::error::LastError;
 use crate::slam::SlamStream;
-use crate::types::SlamMode;
-use std::ffi::{c_char, c_int};
+use crate::types::{CoordinateFrame, Pose, RotationSource, SlamMode};
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CString};
 use std::time::Duration;
 
-/// Thread-local last error message for C consumers.
-static LAST_ERROR: LastError = LastError::new();
+thread_local! {
+    /// Last error message and code of the calling thread, for C consumers.
+    static LAST_ERROR: RefCell<LastError> = const { RefCell::new(LastError::new()) };
+}
+
+fn set_last_error(e: &crate::XvisioError) {
+    LAST_ERROR.with_borrow_mut(|last| last.set(e));
+}
+
+/// No error has occurred yet. This and the `XV_ERR_*` codes of
+/// `xv_last_error_code` keep their values across releases.
+pub const XV_OK: c_int = 0;
+/// An error without a more specific code; see `xv_last_error` for details.
+pub const XV_ERR_OTHER: c_int = 1;
+/// No XR50 is connected.
+pub const XV_ERR_DEVICE_NOT_FOUND: c_int = 2;
+/// The device dropped off the bus, e.g. re-enumerated; re-opening may succeed.
+pub const XV_ERR_DISCONNECTED: c_int = 3;
+/// No data arrived in time.
+pub const XV_ERR_TIMEOUT: c_int = 4;
+/// The OS denied access to the device (udev rule or sudo missing).
+pub const XV_ERR_PERMISSION: c_int = 5;
+/// Another process or driver holds the device.
+pub const XV_ERR_BUSY: c_int = 6;
+/// The SLAM stream has stopped.
+pub const XV_ERR_STREAM_STOPPED: c_int = 7;
+/// The SLAM stream was stopped because the device timestamp stopped advancing.
+pub const XV_ERR_STREAM_STALLED: c_int = 8;
+/// The device doesn't identify as an XR50.
+pub const XV_ERR_UNSUPPORTED_DEVICE: c_int = 9;
+/// The device answered a command with a malformed or rejecting response.
+pub const XV_ERR_PROTOCOL: c_int = 10;
+
+/// Map an error to its `XV_ERR_*` code.
+pub(crate) fn error_code(err: &crate::XvisioError) -> c_int {
+    use crate::XvisioError as E;
+    match err {
+        E::DeviceNotFound => XV_ERR_DEVICE_NOT_FOUND,
+        e if e.is_disconnect() => XV_ERR_DISCONNECTED,
+        E::Timeout | E::FirstSampleTimeout(_) => XV_ERR_TIMEOUT,
+        E::Io(e) if e.kind() == std::io::ErrorKind::PermissionDenied => XV_ERR_PERMISSION,
+        #[cfg(feature = "usb")]
+        E::UsbAccess(_) => XV_ERR_PERMISSION,
+        #[cfg(feature = "usb")]
+        E::UsbBusy(_) => XV_ERR_BUSY,
+        E::StreamStopped | E::ChannelDisconnected => XV_ERR_STREAM_STOPPED,
+        E::StreamStalled => XV_ERR_STREAM_STALLED,
+        E::UnsupportedDevice { .. } => XV_ERR_UNSUPPORTED_DEVICE,
+        E::InvalidResponse(_)
+        | E::InvalidString { .. }
+        | E::StillStreaming
+        | E::CommandMismatch
+        | E::CommandRejected(_)
+        | E::CommandTooLong(_) => XV_ERR_PROTOCOL,
+        _ => XV_ERR_OTHER,
+    }
+}
 
 /// Opaque device handle for C consumers.
-pub struct XvDevice(Device);
+pub struct XvDevice {
+    device: Device,
+    /// Null-terminated copies for `xv_device_uuid` and `xv_device_version`.
+    uuid: CString,
+    version: CString,
+}
+
+impl XvDevice {
+    fn new(device: Device) -> Self {
+        Self {
+            uuid: CString::new(device.uuid()).unwrap_or_default(),
+            version: CString::new(device.version()).unwrap_or_default(),
+            device,
+        }
+    }
+}
 
 /// Opaque SLAM stream handle for C consumers.
 pub struct XvSlamStream(SlamStream);
@@ -32,10 +103,14 @@ pub struct XvPose {
     pub timestamp_us: u64,
     /// Host steady-clock timestamp in seconds.
     pub host_timestamp_s: f64,
-    /// Tracking confidence [0..1].
+    /// Clamped, scaled `confidence_raw`; a tracking hint, not a graded quality.
     pub confidence: f64,
     /// Euler angles [roll, pitch, yaw] in degrees.
     pub euler_deg: [f64; 3],
+    /// Unscaled packet word that `confidence` is derived from.
+    pub confidence_raw: i16,
+    /// Decoded rotation payload: 0 = matrix, 1 = quaternion.
+    pub rotation_source: u8,
 }
 
 /// Device info in C-compatible layout.
@@ -51,6 +126,13 @@ pub struct XvDeviceInfo {
     pub bus_id: [c_char; 32],
     /// USB device address.
     pub address: u8,
+    /// USB bus number. With `address`, identifies the device when `bus_id` is truncated.
+    pub bus_number: u8,
+    /// HID usage page of the command collection, 0 if unknown. Selects the same
+    /// collection again in `xv_open_device`.
+    pub usage_page: u16,
+    /// HID usage of the command collection.
+    pub usage: u16,
 }
 
 fn str_to_fixed<const N: usize>(s: &str) -> [c_char; N] {
@@ -63,10 +145,59 @@ fn str_to_fixed<const N: usize>(s: &str) -> [c_char; N] {
     buf
 }
 
+fn to_xv_pose(pose: &Pose) -> XvPose {
+    let r = &pose.rotation;
+    XvPose {
+        translation: pose.translation,
+        rotation: [
+            r[0][0], r[0][1], r[0][2], r[1][0], r[1][1], r[1][2], r[2][0], r[2][1], r[2][2],
+        ],
+        quaternion: pose.quaternion,
+        timestamp_us: pose.timestamp_us,
+        host_timestamp_s: pose.host_timestamp_s,
+        confidence: pose.confidence,
+        euler_deg: pose.euler_deg,
+        confidence_raw: pose.confidence_raw,
+        rotation_source: pose.rotation_source as u8,
+    }
+}
+
+fn from_xv_pose(pose: &XvPose) -> Pose {
+    let r = &pose.rotation;
+    Pose {
+        translation: pose.translation,
+        rotation: [[r[0], r[1], r[2]], [r[3], r[4], r[5]], [r[6], r[7], r[8]]],
+        quaternion: pose.quaternion,
+        timestamp_us: pose.timestamp_us,
+        host_timestamp_s: pose.host_timestamp_s,
+        confidence: pose.confidence,
+        euler_deg: pose.euler_deg,
+        confidence_raw: pose.confidence_raw,
+        rotation_source: match pose.rotation_source {
+            0 => RotationSource::Matrix,
+            _ => RotationSource::Quaternion,
+        },
+    }
+}
+
+fn to_xv_device_info(dev: &crate::types::DeviceInfo) -> XvDeviceInfo {
+    XvDeviceInfo {
+        uuid: str_to_fixed(&dev.uuid),
+        version: str_to_fixed(&dev.version),
+        features: dev.features.bits(),
+        bus_id: str_to_fixed(&dev.bus_id),
+        address: dev.device_address,
+        bus_number: dev.bus_number,
+        usage_page: dev.usage_page,
+        usage: dev.usage,
+    }
+}
+
 /// List connected XR50 devices.
 ///
-/// Writes up to `max` entries into `out`. Returns the number of devices found,
-/// or -1 on error.
+/// Writes up to `max` entries into `out` and returns the number written, or -1
+/// on error. With `out` null or `max` <= 0 nothing is written and the total
+/// number of devices is returned, so callers can size the array first.
 ///
 /// # Safety
 /// `out` must point to an array of at least `max` `XvDeviceInfo` elements, or be null.
@@ -74,23 +205,30 @@ fn str_to_fixed<const N: usize>(s: &str) -> [c_char; N] {
 pub unsafe extern "C" fn xv_list_devices(out: *mut XvDeviceInfo, max: c_int) -> c_int {
     match crate::device::list_devices() {
         Ok(devices) => {
+            if out.is_null() || max <= 0 {
+                return devices.len() as c_int;
+            }
             let count = devices.len().min(max as usize);
-            if !out.is_null() {
-                for (i, dev) in devices.iter().take(count).enumerate() {
-                    let info = XvDeviceInfo {
-                        uuid: str_to_fixed(&dev.uuid),
-                        version: str_to_fixed(&dev.version),
-                        features: dev.features.bits(),
-                        bus_id: str_to_fixed(&dev.bus_id),
-                        address: dev.device_address,
-                    };
-                    out.add(i).write(info);
-                }
+            for (i, dev) in devices.iter().take(count).enumerate() {
+                out.add(i).write(to_xv_device_info(dev));
             }
             count as c_int
         }
         Err(e) => {
-            LAST_ERROR.set(&e);
+            set_last_error(&e);
+            -1
+        }
+    }
+}
+
+/// Count connected XR50 devices from USB enumeration alone, without opening
+/// them: cheap enough for a presence poll. Returns -1 on error.
+#[no_mangle]
+pub extern "C" fn xv_device_count() -> c_int {
+    match crate::device::list_devices_fast() {
+        Ok(devices) => devices.len() as c_int,
+        Err(e) => {
+            set_last_error(&e);
             -1
         }
     }
@@ -101,9 +239,9 @@ pub unsafe extern "C" fn xv_list_devices(out: *mut XvDeviceInfo, max: c_int) ->
 #[no_mangle]
 pub extern "C" fn xv_open_first() -> *mut XvDevice {
     match Device::open_first() {
-        Ok(dev) => Box::into_raw(Box::new(XvDevice(dev))),
+        Ok(dev) => Box::into_raw(Box::new(XvDevice::new(dev))),
         Err(e) => {
-            LAST_ERROR.set(&e);
+            set_last_error(&e);
             std::ptr::null_mut()
         }
     }
@@ -129,15 +267,20 @@ pub unsafe extern "C" fn xv_open_device(info: *const XvDeviceInfo) -> *mut XvDev
     let dev_info = crate::types::DeviceInfo {
         uuid,
         version,
-        features: crate::types::Features::from_bits_truncate(info.features),
+        features: crate::types::Features::from_bits_retain(info.features),
         bus_id,
+        bus_number: info.bus_number,
         device_address: info.address,
+        usage_page: info.usage_page,
+        usage: info.usage,
+        serial_number: None,
+        error: None,
     };
 
     match Device::open(&dev_info) {
-        Ok(dev) => Box::into_raw(Box::new(XvDevice(dev))),
+        Ok(dev) => Box::into_raw(Box::new(XvDevice::new(dev))),
         Err(e) => {
-            LAST_ERROR.set(&e);
+            set_last_error(&e);
             std::ptr::null_mut()
         }
     }
@@ -164,8 +307,7 @@ pub unsafe extern "C" fn xv_device_uuid(dev: *const XvDevice) -> *const c_char {
     if dev.is_null() {
         return std::ptr::null();
     }
-    let dev = &*dev;
-    dev.0.uuid().as_ptr() as *const c_char
+    (*dev).uuid.as_ptr()
 }
 
 /// Get the device firmware version string.
@@ -177,8 +319,7 @@ pub unsafe extern "C" fn xv_device_version(dev: *const XvDevice) -> *const c_cha
     if dev.is_null() {
         return std::ptr::null();
     }
-    let dev = &*dev;
-    dev.0.version().as_ptr() as *const c_char
+    (*dev).version.as_ptr()
 }
 
 /// Get the device feature bitmap.
@@ -191,7 +332,23 @@ pub unsafe extern "C" fn xv_device_features(dev: *const XvDevice) -> u32 {
         return 0;
     }
     let dev = &*dev;
-    dev.0.features().bits()
+    dev.device.features().bits()
+}
+
+/// Get the full device info of an open device.
+/// Returns 0 on success, -1 if either pointer is null.
+///
+/// # Safety
+/// `dev` must be a valid device pointer, or null.
+/// `out` must point to a writable `XvDeviceInfo`, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn xv_device_info(dev: *const XvDevice, out: *mut XvDeviceInfo) -> c_int {
+    if dev.is_null() || out.is_null() {
+        return -1;
+    }
+    let dev = &*dev;
+    out.write(to_xv_device_info(&dev.device.info()));
+    0
 }
 
 /// Start SLAM streaming.
@@ -212,10 +369,10 @@ pub unsafe extern "C" fn xv_start_slam(dev: *mut XvDevice, mode: c_int) -> *mut
         _ => SlamMode::Edge,
     };
 
-    match dev.0.start_slam(slam_mode) {
+    match dev.device.start_slam(slam_mode) {
         Ok(stream) => Box::into_raw(Box::new(XvSlamStream(stream))),
         Err(e) => {
-            LAST_ERROR.set(&e);
+            set_last_error(&e);
             std::ptr::null_mut()
         }
     }
@@ -250,35 +407,66 @@ pub unsafe extern "C" fn xv_slam_recv(
 
     match result {
         Ok(sample) => {
-            let out = XvPose {
-                translation: sample.pose.translation,
-                rotation: [
-                    sample.pose.rotation[0][0],
-                    sample.pose.rotation[0][1],
-                    sample.pose.rotation[0][2],
-                    sample.pose.rotation[1][0],
-                    sample.pose.rotation[1][1],
-                    sample.pose.rotation[1][2],
-                    sample.pose.rotation[2][0],
-                    sample.pose.rotation[2][1],
-                    sample.pose.rotation[2][2],
-                ],
-                quaternion: sample.pose.quaternion,
-                timestamp_us: sample.pose.timestamp_us,
-                host_timestamp_s: sample.pose.host_timestamp_s,
-                confidence: sample.pose.confidence,
-                euler_deg: sample.pose.euler_deg,
-            };
-            pose.write(out);
+            pose.write(to_xv_pose(&sample.pose));
             0
         }
         Err(e) => {
-            LAST_ERROR.set(&e);
+            set_last_error(&e);
             -1
         }
     }
 }
 
+/// Convert a pose from the XR50 device frame into another coordinate frame.
+/// `frame`: 0 = Device, 1 = OpenXR/OpenGL/Three.js (right-handed, -Z forward),
+/// 2 = Unity (left-handed, Z forward).
+/// `in_pose` and `out` may point to the same pose.
+/// Returns 0 on success, -1 on a null pointer or unknown frame.
+///
+/// # Safety
+/// `in_pose` must point to a valid `XvPose` and `out` to a writable one, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn xv_pose_to_frame(
+    in_pose: *const XvPose,
+    frame: c_int,
+    out: *mut XvPose,
+) -> c_int {
+    if in_pose.is_null() || out.is_null() {
+        return -1;
+    }
+    let frame = match frame {
+        0 => CoordinateFrame::Device,
+        1 => CoordinateFrame::OpenXr,
+        2 => CoordinateFrame::Unity,
+        _ => return -1,
+    };
+    let pose = from_xv_pose(&*in_pose).to_frame(frame);
+    out.write(to_xv_pose(&pose));
+    0
+}
+
+/// Rotation of `pose` as a unit axis, written to `axis[0..3]`, and an angle in
+/// radians within [0, π], written to `angle`. Near identity the axis is [1, 0, 0].
+/// Returns 0 on success, -1 on a null pointer.
+///
+/// # Safety
+/// `pose` must point to a valid `XvPose`, `axis` to 3 writable doubles and
+/// `angle` to a writable double, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn xv_pose_axis_angle(
+    pose: *const XvPose,
+    axis: *mut f64,
+    angle: *mut f64,
+) -> c_int {
+    if pose.is_null() || axis.is_null() || angle.is_null() {
+        return -1;
+    }
+    let (unit, radians) = from_xv_pose(&*pose).axis_angle();
+    std::ptr::copy_nonoverlapping(unit.as_ptr(), axis, 3);
+    angle.write(radians);
+    0
+}
+
 /// Check if the SLAM stream is still active.
 ///
 /// # Safety
@@ -292,6 +480,19 @@ pub unsafe extern "C" fn xv_slam_is_active(stream: *const XvSlamStream) -> bool
     stream.0.is_active()
 }
 
+/// Check if the SLAM stream has stalled (device timestamp no longer advancing).
+///
+/// # Safety
+/// `stream` must be a valid stream pointer, or null.
+#[no_mangle]
+pub unsafe extern "C" fn xv_slam_is_stalled(stream: *const XvSlamStream) -> bool {
+    if stream.is_null() {
+        return false;
+    }
+    let stream = &*stream;
+    stream.0.is_stalled()
+}
+
 /// Stop a SLAM stream and free its resources.
 ///
 /// # Safety
@@ -303,11 +504,19 @@ pub unsafe extern "C" fn xv_stop_slam(stream: *mut XvSlamStream) {
     }
 }
 
-/// Get the last error message. Returns NULL if no error.
-/// The returned pointer is valid until the next xvisio API call.
+/// Get the calling thread's last error message. Returns NULL if no error.
+/// The returned pointer is valid until the next xvisio API call on this thread.
 #[no_mangle]
 pub extern "C" fn xv_last_error() -> *const c_char {
-    LAST_ERROR.as_ptr()
+    LAST_ERROR.with_borrow(LastError::as_ptr)
+}
+
+/// Get the code of the calling thread's last error, one of the `XV_ERR_*`
+/// constants, or `XV_OK` if none has occurred. Set together with `xv_last_error`;
+/// calls that fail on a null argument leave both unchanged.
+#[no_mangle]
+pub extern "C" fn xv_last_error_code() -> c_int {
+    LAST_ERROR.with_borrow(LastError::code)
 }
 
 fn c_char_to_string(buf: &[c_char]) -> String {
@@ -1,32 +1,122 @@
-use crate::protocol::{self, PREFIX_DEVICE_TO_HOST, REPORT_SIZE};
+use crate::protocol::{self, PREFIX_DEVICE_TO_HOST, REPORT_SIZE, SLAM_HEADER};
 use crate::{Result, XvisioError};
 use hidapi::HidDevice;
+use std::time::{Duration, Instant};
+
+/// Shortest plausible UUID and version strings; real ones are ~20 characters.
+const MIN_UUID_LEN: usize = 8;
+const MIN_VERSION_LEN: usize = 4;
+
+/// Raw report I/O underneath `HidTransport`, mirroring the hidapi calls it uses.
+/// Tests substitute a mock that answers commands with canned reports.
+pub trait Transport {
+    /// Send an output report; `byte[0]` is the report ID.
+    fn write(&self, data: &[u8]) -> Result<usize>;
+    /// Fetch an input report; `buf[0]` selects the report ID.
+    fn get_input_report(&self, buf: &mut [u8]) -> Result<usize>;
+    /// Read from the interrupt pipe, returning 0 on timeout.
+    fn read_timeout(&self, buf: &mut [u8], timeout_ms: i32) -> Result<usize>;
+    /// Fetch the HID report descriptor of the command interface.
+    fn get_report_descriptor(&self, buf: &mut [u8]) -> Result<usize>;
+}
+
+impl Transport for HidDevice {
+    fn write(&self, data: &[u8]) -> Result<usize> {
+        Ok(HidDevice::write(self, data)?)
+    }
+
+    fn get_input_report(&self, buf: &mut [u8]) -> Result<usize> {
+        Ok(HidDevice::get_input_report(self, buf)?)
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], timeout_ms: i32) -> Result<usize> {
+        Ok(HidDevice::read_timeout(self, buf, timeout_ms)?)
+    }
+
+    fn get_report_descriptor(&self, buf: &mut [u8]) -> Result<usize> {
+        Ok(HidDevice::get_report_descriptor(self, buf)?)
+    }
+}
+
+/// The same reports over raw control and interrupt transfers, for a device
+/// opened through libusb (e.g. from an Android file descriptor).
+#[cfg(feature = "usb")]
+impl Transport for rusb::DeviceHandle<rusb::GlobalContext> {
+    fn write(&self, data: &[u8]) -> Result<usize> {
+        let report_id = data.first().copied().unwrap_or(0) as u16;
+        Ok(self.write_control(
+            0x21,
+            0x09,
+            0x0200 | report_id,
+            protocol::HID_INTERFACE as u16,
+            data,
+            USB_CONTROL_TIMEOUT,
+        )?)
+    }
+
+    fn get_input_report(&self, buf: &mut [u8]) -> Result<usize> {
+        let report_id = buf.first().copied().unwrap_or(0) as u16;
+        Ok(self.read_control(
+            0xA1,
+            0x01,
+            0x0100 | report_id,
+            protocol::HID_INTERFACE as u16,
+            buf,
+            USB_CONTROL_TIMEOUT,
+        )?)
+    }
+
+    fn read_timeout(&self, buf: &mut [u8], timeout_ms: i32) -> Result<usize> {
+        let timeout = Duration::from_millis(timeout_ms.max(1) as u64);
+        match self.read_interrupt(protocol::SLAM_ENDPOINT, buf, timeout) {
+            Err(rusb::Error::Timeout) => Ok(0),
+            result => Ok(result?),
+        }
+    }
+
+    fn get_report_descriptor(&self, buf: &mut [u8]) -> Result<usize> {
+        // GET_DESCRIPTOR, descriptor type 0x22 (HID report), to the interface.
+        Ok(self.read_control(
+            0x81,
+            0x06,
+            0x2200,
+            protocol::HID_INTERFACE as u16,
+            buf,
+            USB_CONTROL_TIMEOUT,
+        )?)
+    }
+}
+
+#[cfg(feature = "usb")]
+const USB_CONTROL_TIMEOUT: Duration = Duration::from_secs(1);
 
 /// HID transport layer using hidapi for SET_REPORT / GET_REPORT.
 ///
-/// On Windows, hidapi's `write()` uses byte[0] as the HID report ID.
+/// On Windows, hidapi's `write()` uses `byte[0]` as the HID report ID.
 /// The XR50 protocol prefix 0x02 (host-to-device) doubles as the output
 /// report ID, so `build_command()` output (63 bytes starting with 0x02)
 /// can be passed directly to `write()`.
-pub struct HidTransport {
-    device: HidDevice,
+pub struct HidTransport<T: Transport + ?Sized = HidDevice> {
+    device: T,
 }
 
-impl HidTransport {
-    pub fn new(device: HidDevice) -> Self {
+impl<T: Transport> HidTransport<T> {
+    pub fn new(device: T) -> Self {
         Self { device }
     }
 
     /// Consume the transport and return the inner HID device handle.
     /// Used on macOS where exclusive access prevents opening a second handle.
-    pub fn into_device(self) -> HidDevice {
+    pub fn into_device(self) -> T {
         self.device
     }
+}
 
+impl<T: Transport + ?Sized> HidTransport<T> {
     /// Send a HID command and receive the response.
     ///
     /// 1. Builds a 63-byte buffer: [0x02, cmd_bytes..., padding]
-    /// 2. Sends via `write()` — byte[0]=0x02 serves as both report ID and protocol prefix
+    /// 2. Sends via `write()` — `byte[0]=0x02` serves as both report ID and protocol prefix
     /// 3. Reads via `get_input_report()` — report ID 0x01 = device-to-host prefix
     /// 4. Validates response prefix and command echo
     pub fn transaction(&self, cmd: &[u8]) -> Result<Vec<u8>> {
@@ -63,41 +153,68 @@ impl HidTransport {
 
     /// Read UUID string from the device.
     pub fn read_uuid(&self) -> Result<String> {
-        let response = self.transaction(protocol::CMD_UUID)?;
-        let offset = protocol::validate_response(&response, protocol::CMD_UUID)?;
-        Ok(protocol::extract_string(&response[offset..]))
+        self.read_string(protocol::CMD_UUID, "UUID", MIN_UUID_LEN)
     }
 
     /// Read firmware version string from the device.
     pub fn read_version(&self) -> Result<String> {
-        let response = self.transaction(protocol::CMD_VERSION)?;
-        let offset = protocol::validate_response(&response, protocol::CMD_VERSION)?;
-        Ok(protocol::extract_string(&response[offset..]))
+        self.read_string(protocol::CMD_VERSION, "version", MIN_VERSION_LEN)
+    }
+
+    /// Run a string query, failing with [`XvisioError::InvalidString`] on garbage
+    /// and [`XvisioError::StillStreaming`] when a SLAM packet answers instead.
+    fn read_string(&self, cmd: &[u8], field: &'static str, min_len: usize) -> Result<String> {
+        let response = self.transaction(cmd)?;
+        if response.starts_with(&SLAM_HEADER) {
+            return Err(XvisioError::StillStreaming);
+        }
+        let response = protocol::Response::parse(&response, cmd).check()?;
+        protocol::identity_string(response.payload, min_len)
+            .map(str::to_string)
+            .map_err(|raw| XvisioError::InvalidString {
+                field,
+                raw: raw.to_vec(),
+            })
+    }
+
+    /// Read the HID report descriptor, e.g. to check report sizes on unfamiliar
+    /// firmware. The returned length is the descriptor's.
+    pub fn report_descriptor(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![0u8; hidapi::MAX_REPORT_DESCRIPTOR_SIZE];
+        let len = self.device.get_report_descriptor(&mut buf)?;
+        buf.truncate(len);
+        Ok(buf)
     }
 
     /// Read features bitmap from the device.
     pub fn read_features(&self) -> Result<crate::types::Features> {
         let response = self.transaction(protocol::CMD_FEATURES)?;
-        let offset = protocol::validate_response(&response, protocol::CMD_FEATURES)?;
-        Ok(protocol::parse_features(&response[offset..]))
+        let response = protocol::Response::parse(&response, protocol::CMD_FEATURES).check()?;
+        Ok(protocol::parse_features(response.payload))
     }
 
-    /// Send the configure command for the given SLAM mode and UVC mode.
-    pub fn configure_with_uvc(&self, edge: bool, uvc_mode: u8, embedded_algo: bool) -> Result<()> {
-        let cmd_buf = protocol::build_configure_cmd_with_uvc(edge, uvc_mode, embedded_algo);
-
-        self.device
-            .write(&cmd_buf)
-            .map_err(|e| XvisioError::HidCommand(format!("Configure write failed: {}", e)))?;
+    /// Send a fire-and-forget command, failing only if the device rejects it.
+    pub fn send_acked(&self, cmd: &protocol::CommandBuilder) -> Result<()> {
+        self.device.write(&cmd.build()).map_err(|e| {
+            XvisioError::HidCommand(format!("{:02x?} write failed: {}", cmd.echo(), e))
+        })?;
 
         std::thread::sleep(std::time::Duration::from_millis(20));
 
-        // Read response (may be all zeros, that's OK)
+        // Read response (may be all zeros, that's OK; a wrong echo is a rejection)
         let mut recv_buf = [0u8; REPORT_SIZE + 1];
         recv_buf[0] = PREFIX_DEVICE_TO_HOST;
-        let _ = self.device.get_input_report(&mut recv_buf);
+        let len = self.device.get_input_report(&mut recv_buf).unwrap_or(0);
+        protocol::check_ack(&recv_buf[..len], cmd.echo())
+    }
 
-        Ok(())
+    /// Send the configure command for the given SLAM mode and UVC mode.
+    pub fn configure_with_uvc(&self, edge: bool, uvc_mode: u8, embedded_algo: bool) -> Result<()> {
+        self.send_acked(&protocol::CommandBuilder::configure(
+            edge,
+            uvc_mode,
+            embedded_algo,
+        ))
     }
 
     /// Send the configure command for the given SLAM mode.
@@ -112,20 +229,11 @@ impl HidTransport {
         rotation_enabled: bool,
         flipped: bool,
     ) -> Result<()> {
-        let cmd_buf =
-            protocol::build_edge_stream_cmd_with_params(edge_mode, rotation_enabled, flipped);
-
-        self.device
-            .write(&cmd_buf)
-            .map_err(|e| XvisioError::HidCommand(format!("Edge stream cmd failed: {}", e)))?;
-
-        std::thread::sleep(std::time::Duration::from_millis(20));
-
-        let mut recv_buf = [0u8; REPORT_SIZE + 1];
-        recv_buf[0] = PREFIX_DEVICE_TO_HOST;
-        let _ = self.device.get_input_report(&mut recv_buf);
-
-        Ok(())
+        self.send_acked(&protocol::CommandBuilder::edge_stream(
+            edge_mode,
+            rotation_enabled,
+            flipped,
+        ))
     }
 
     /// Send the start/stop edge stream command.
@@ -133,6 +241,34 @@ impl HidTransport {
         self.edge_stream_with_params(if start { 1 } else { 0 }, start, false)
     }
 
+    /// Listen up to `timeout` for a SLAM packet on the interrupt pipe.
+    pub fn is_streaming(&self, timeout: Duration) -> Result<bool> {
+        let mut buf = [0u8; REPORT_SIZE + 1];
+        let deadline = Instant::now() + timeout;
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            let len = self
+                .device
+                .read_timeout(&mut buf, remaining.as_millis() as i32)?;
+            if len == 0 {
+                break;
+            }
+            if buf[..len].starts_with(&SLAM_HEADER) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Stop the edge stream and drain input reports queued before it took effect.
+    /// Draining is capped so a device that ignores the stop can't hang the caller.
+    pub fn reset(&self) -> Result<()> {
+        self.edge_stream(false)?;
+        let mut buf = [0u8; REPORT_SIZE + 1];
+        let deadline = Instant::now() + Duration::from_millis(200);
+        while Instant::now() < deadline && self.device.read_timeout(&mut buf, 10)? > 0 {}
+        Ok(())
+    }
+
     /// Send stereo camera init command.
     pub fn stereo_camera_init(&self) -> Result<()> {
         let _ = self.transaction(protocol::CMD_STEREO_CAMERA_INIT)?;
@@ -145,3 +281,142 @@ impl HidTransport {
         Ok(())
     }
 }
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::Transport;
+    use crate::protocol::{CMD_EDGE_STREAM, PREFIX_DEVICE_TO_HOST, REPORT_SIZE, SLAM_HEADER};
+    use crate::Result;
+    use std::cell::{Cell, RefCell};
+
+    /// Answers each command with its echo followed by a canned payload.
+    /// Unknown commands get a zero-length report. While `streaming`, every
+    /// command is answered with a SLAM packet until an edge stream stop.
+    #[derive(Default)]
+    pub(crate) struct MockTransport {
+        responses: Vec<(Vec<u8>, Vec<u8>)>,
+        last_write: RefCell<Vec<u8>>,
+        streaming: Cell<bool>,
+    }
+
+    impl MockTransport {
+        pub(crate) fn respond(mut self, cmd: &[u8], payload: &[u8]) -> Self {
+            self.responses.push((cmd.to_vec(), payload.to_vec()));
+            self
+        }
+
+        /// Start as if a previous session left edge streaming on.
+        pub(crate) fn streaming(self) -> Self {
+            self.streaming.set(true);
+            self
+        }
+    }
+
+    impl Transport for MockTransport {
+        fn write(&self, data: &[u8]) -> Result<usize> {
+            if data
+                .get(1..4)
+                .is_some_and(|c| c[..2] == *CMD_EDGE_STREAM && c[2] == 0)
+            {
+                self.streaming.set(false);
+            }
+            *self.last_write.borrow_mut() = data.to_vec();
+            Ok(data.len())
+        }
+
+        fn get_input_report(&self, buf: &mut [u8]) -> Result<usize> {
+            if self.streaming.get() {
+                buf[..REPORT_SIZE].fill(0);
+                buf[..SLAM_HEADER.len()].copy_from_slice(&SLAM_HEADER);
+                return Ok(REPORT_SIZE);
+            }
+            let sent = self.last_write.borrow();
+            let Some((cmd, payload)) = self
+                .responses
+                .iter()
+                .find(|(cmd, _)| sent.get(1..).is_some_and(|s| s.starts_with(cmd)))
+            else {
+                return Ok(0);
+            };
+            let report: Vec<u8> = [&[PREFIX_DEVICE_TO_HOST][..], cmd, payload].concat();
+            buf[..report.len()].copy_from_slice(&report);
+            Ok(report.len())
+        }
+
+        fn read_timeout(&self, _buf: &mut [u8], _timeout_ms: i32) -> Result<usize> {
+            Ok(0)
+        }
+
+        fn get_report_descriptor(&self, _buf: &mut [u8]) -> Result<usize> {
+            Ok(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::MockTransport;
+    use super::*;
+    use crate::types::Features;
+
+    #[test]
+    fn test_read_uuid_from_mock() {
+        let hid =
+            HidTransport::new(MockTransport::default().respond(protocol::CMD_UUID, b"XR50-1234\0"));
+        assert_eq!(hid.read_uuid().unwrap(), "XR50-1234");
+    }
+
+    #[test]
+    fn test_read_uuid_rejects_garbage() {
+        for garbage in [&b"\xff\xfeXR50-12\0"[..], b"XR5\0", b"\0"] {
+            let hid =
+                HidTransport::new(MockTransport::default().respond(protocol::CMD_UUID, garbage));
+            assert!(matches!(
+                hid.read_uuid(),
+                Err(XvisioError::InvalidString { field: "UUID", .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_read_features_from_mock() {
+        let hid = HidTransport::new(
+            MockTransport::default().respond(protocol::CMD_FEATURES, &[0x01, 0x00, 0x00, 0x00]),
+        );
+        assert!(hid.read_features().unwrap().contains(Features::EDGE_MODE));
+    }
+
+    #[test]
+    fn test_zero_length_report_is_invalid() {
+        let hid = HidTransport::new(MockTransport::default());
+        assert!(matches!(
+            hid.read_version(),
+            Err(XvisioError::InvalidResponse(0))
+        ));
+    }
+
+    #[test]
+    fn test_configure_rejected() {
+        let nak = HidTransport::new(
+            MockTransport::default().respond(&protocol::CMD_CONFIGURE[..1], &[0x00]),
+        );
+        assert!(matches!(
+            nak.configure(true, false),
+            Err(XvisioError::CommandRejected(_))
+        ));
+        // No answer at all is tolerated, as is a SLAM packet from a stream left on.
+        assert!(HidTransport::new(MockTransport::default())
+            .configure(true, false)
+            .is_ok());
+        assert!(HidTransport::new(MockTransport::default().streaming())
+            .configure(true, false)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_command_echo_mismatch() {
+        let hid =
+            HidTransport::new(MockTransport::default().respond(&[protocol::CMD_UUID[0]], b""));
+        assert!(hid.read_uuid().is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! Helpers for the (still hypothetical, see PROTOCOL.md) IMU fields of a sample.
+
+use crate::types::ImuData;
+
+/// Standard gravity in m/s², the magnitude [`ImuCalibration::at_rest`] scales to.
+pub const STANDARD_GRAVITY: f64 = 9.80665;
+
+/// Linear correction of the raw IMU fields: `(raw - bias) * scale` per sensor.
+///
+/// [`Default`] is the identity, leaving the hypothetical raw units (g and
+/// unconfirmed gyro units, see [`ImuData`]) untouched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImuCalibration {
+    pub accel_scale: f64,
+    pub gyro_scale: f64,
+    pub accel_bias: [f64; 3],
+    pub gyro_bias: [f64; 3],
+}
+
+impl Default for ImuCalibration {
+    fn default() -> Self {
+        Self {
+            accel_scale: 1.0,
+            gyro_scale: 1.0,
+            accel_bias: [0.0; 3],
+            gyro_bias: [0.0; 3],
+        }
+    }
+}
+
+impl ImuCalibration {
+    /// Calibration from samples of a device at rest: the accelerometer scale that
+    /// makes the mean magnitude [`STANDARD_GRAVITY`] (so accel reads m/s²) and the
+    /// mean gyro reading as gyro bias. One orientation can't separate the per-axis
+    /// accel bias from gravity, nor reveal the gyro scale, so those stay identity.
+    ///
+    /// `None` for no samples or a zero accelerometer.
+    pub fn at_rest(samples: &[ImuData]) -> Option<ImuCalibration> {
+        if samples.is_empty() {
+            return None;
+        }
+        let n = samples.len() as f64;
+        let mut magnitude = 0.0;
+        let mut gyro_bias = [0.0; 3];
+        for imu in samples {
+            magnitude += crate::math::sqrt(imu.accelerometer.iter().map(|a| a * a).sum());
+            for (b, g) in gyro_bias.iter_mut().zip(imu.gyroscope) {
+                *b += g / n;
+            }
+        }
+        let magnitude = magnitude / n;
+        (magnitude > 0.0).then(|| ImuCalibration {
+            accel_scale: STANDARD_GRAVITY / magnitude,
+            gyro_bias,
+            ..Default::default()
+        })
+    }
+
+    /// Correct `imu` in place.
+    pub fn apply(&self, imu: &mut ImuData) {
+        for (a, b) in imu.accelerometer.iter_mut().zip(self.accel_bias) {
+            *a = (*a - b) * self.accel_scale;
+        }
+        for (g, b) in imu.gyroscope.iter_mut().zip(self.gyro_bias) {
+            *g = (*g - b) * self.gyro_scale;
+        }
+    }
+}
+
+/// Estimates the static gyroscope bias from the periods the device sits still.
+///
+/// Samples are grouped into windows; a window counts as still when the summed
+/// per-axis accelerometer variance stays under the threshold. That rules out
+/// translation and tilting, but not a constant-rate turn about gravity, which
+/// leaves the accelerometer unchanged and would be absorbed into the bias; set
+/// [`max_rate`](Self::max_rate) to reject those windows too. Each still window's
+/// mean gyro reading updates the bias, averaged over the last ~10 still windows
+/// so slow drift is followed.
+#[derive(Debug, Clone)]
+pub struct GyroBiasEstimator {
+    window: u32,
+    threshold: f64,
+    max_rate: Option<f64>,
+    count: u32,
+    accel_sum: [f64; 3],
+    accel_sq_sum: [f64; 3],
+    gyro_sum: [f64; 3],
+    still_windows: u32,
+    bias: Option<[f64; 3]>,
+}
+
+impl Default for GyroBiasEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GyroBiasEstimator {
+    /// Still windows averaged into the bias before older ones start fading out.
+    const MAX_WEIGHT: u32 = 10;
+
+    /// 500-sample windows (~0.5 s at 950 Hz) and a variance threshold of 1e-4 g².
+    pub fn new() -> Self {
+        Self {
+            window: 500,
+            threshold: 1e-4,
+            max_rate: None,
+            count: 0,
+            accel_sum: [0.0; 3],
+            accel_sq_sum: [0.0; 3],
+            gyro_sum: [0.0; 3],
+            still_windows: 0,
+            bias: None,
+        }
+    }
+
+    /// Set the samples per window.
+    pub fn window(mut self, samples: u32) -> Self {
+        self.window = samples.max(2);
+        self
+    }
+
+    /// Set the largest summed accelerometer variance (g²) that counts as still.
+    pub fn threshold(mut self, variance: f64) -> Self {
+        self.threshold = variance;
+        self
+    }
+
+    /// Also require the window's mean gyro magnitude to stay under `rate`, in gyro
+    /// units: larger than the bias can be, smaller than the slowest turn to ignore.
+    pub fn max_rate(mut self, rate: f64) -> Self {
+        self.max_rate = Some(rate);
+        self
+    }
+
+    /// Add a sample; returns true when it completed a still window and the bias
+    /// was updated.
+    pub fn push(&mut self, imu: &ImuData) -> bool {
+        for i in 0..3 {
+            self.accel_sum[i] += imu.accelerometer[i];
+            self.accel_sq_sum[i] += imu.accelerometer[i] * imu.accelerometer[i];
+            self.gyro_sum[i] += imu.gyroscope[i];
+        }
+        self.count += 1;
+        if self.count < self.window {
+            return false;
+        }
+
+        let n = self.count as f64;
+        let variance: f64 = (0..3)
+            .map(|i| {
+                let mean = self.accel_sum[i] / n;
+                self.accel_sq_sum[i] / n - mean * mean
+            })
+            .sum();
+        let mean = self.gyro_sum.map(|g| g / n);
+        self.count = 0;
+        self.accel_sum = [0.0; 3];
+        self.accel_sq_sum = [0.0; 3];
+        self.gyro_sum = [0.0; 3];
+        let rate = crate::math::sqrt(mean.iter().map(|g| g * g).sum());
+        if variance > self.threshold || self.max_rate.is_some_and(|max| rate > max) {
+            return false;
+        }
+
+        self.still_windows = (self.still_windows + 1).min(Self::MAX_WEIGHT);
+        let k = self.still_windows as f64;
+        let bias = self.bias.get_or_insert(mean);
+        for (b, m) in bias.iter_mut().zip(mean) {
+            *b += (m - *b) / k;
+        }
+        true
+    }
+
+    /// Current bias estimate, `None` until the first still window.
+    pub fn bias(&self) -> Option<[f64; 3]> {
+        self.bias
+    }
+
+    /// Subtract the current estimate from `imu.gyroscope`; no-op without one.
+    pub fn correct(&self, imu: &mut ImuData) {
+        if let Some(bias) = self.bias {
+            for (g, b) in imu.gyroscope.iter_mut().zip(bias) {
+                *g -= b;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_imu_calibration_at_rest() {
+        let rest = ImuData {
+            accelerometer: [0.0, 0.6, 0.8],
+            gyroscope: [0.1, -0.2, 0.05],
+            ..Default::default()
+        };
+        let calibration = ImuCalibration::at_rest(&[rest; 4]).unwrap();
+        let mut imu = rest;
+        calibration.apply(&mut imu);
+        let g = imu.accelerometer.iter().map(|a| a * a).sum::<f64>().sqrt();
+        assert!((g - STANDARD_GRAVITY).abs() < 1e-9);
+        assert!(imu.gyroscope.iter().all(|g| g.abs() < 1e-12));
+        assert!(ImuCalibration::at_rest(&[]).is_none());
+    }
+
+    #[test]
+    fn test_gyro_bias_from_still_windows_only() {
+        let mut estimator = GyroBiasEstimator::new().window(4);
+        let still = ImuData {
+            accelerometer: [0.0, 0.0, 1.0],
+            gyroscope: [0.1, -0.2, 0.05],
+            ..Default::default()
+        };
+        let moving = |i: usize| ImuData {
+            accelerometer: [i as f64 * 0.5, 0.0, 1.0],
+            gyroscope: [3.0, 3.0, 3.0],
+            ..Default::default()
+        };
+
+        for i in 0..4 {
+            assert!(!estimator.push(&moving(i)));
+        }
+        assert_eq!(estimator.bias(), None);
+
+        let mut turning = GyroBiasEstimator::new().window(4).max_rate(1.0);
+        let yaw = ImuData {
+            gyroscope: [0.0, 0.0, 3.0],
+            ..still
+        };
+        assert_eq!((0..4).filter(|_| turning.push(&yaw)).count(), 0);
+
+        assert_eq!((0..4).filter(|_| estimator.push(&still)).count(), 1);
+        let mut imu = still;
+        estimator.correct(&mut imu);
+        assert!(imu.gyroscope.iter().all(|g| g.abs() < 1e-12));
+    }
+}
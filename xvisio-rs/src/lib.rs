@@ -8,30 +8,82 @@
 //! ## Quick Start
 //! ```no_run
 //! use xvisio::{Device, SlamMode};
-//! use std::time::Duration;
 //!
 //! let mut device = Device::open_first().unwrap();
 //! println!("UUID: {}", device.uuid());
 //!
 //! let stream = device.start_slam(SlamMode::Edge).unwrap();
-//! for _ in 0..100 {
-//!     let sample = stream.recv_timeout(Duration::from_secs(1)).unwrap();
+//! for sample in stream.iter().take(100) {
 //!     println!("pos: {:?}", sample.pose.translation);
 //! }
 //! ```
+//!
+//! ## Logging and tracing
+//! Diagnostics go through the `log` facade; on a `tracing` stack, forward them
+//! with `tracing_log::LogTracer`. For structured startup instrumentation use
+//! `SlamConfig::progress`: every phase, including `StartupPhase::Retrying`
+//! with its attempt number, is reported on the thread running `start_slam`, so
+//! the callback can enter and leave spans. `SlamStream::startup_timings` gives the
+//! phase durations afterwards, and `SlamStream::events` the stream's disconnects
+//! and stalls.
+//!
+//! ## `no_std`
+//! With `default-features = false` only the pure packet parsing (`protocol`, `types`)
+//! is built, on `no_std + alloc`. See [`protocol::parse_pose`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "std")]
+pub mod capture;
+#[cfg(feature = "std")]
 pub mod device;
 pub mod error;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
 pub mod ffi;
+#[cfg(feature = "std")]
 pub mod hid;
+pub mod imu;
+mod math;
+#[cfg(feature = "std")]
+pub mod persistent;
 pub mod protocol;
+#[cfg(feature = "std")]
+mod sched;
+#[cfg(all(feature = "shm", unix))]
+pub mod shm;
+#[cfg(feature = "std")]
 pub mod slam;
 pub mod types;
+#[cfg(feature = "usb")]
+pub mod uvc;
 
-pub use device::Device;
+#[cfg(feature = "std")]
+pub use capture::replay_raw;
+#[cfg(feature = "usb")]
+pub use device::UsbInfo;
+#[cfg(feature = "std")]
+pub use device::{Device, DiagnosticAttempt, DiagnosticReport};
 pub use error::XvisioError;
-pub use slam::SlamStream;
+pub use imu::{GyroBiasEstimator, ImuCalibration};
+#[cfg(feature = "std")]
+pub use persistent::{PersistentDevice, PersistentStream};
+pub use protocol::{NOMINAL_RATE_HZ, PID, REPORT_SIZE, SLAM_ENDPOINT, VID};
+#[cfg(feature = "std")]
+pub use sched::Priority;
+#[cfg(all(feature = "shm", unix))]
+pub use shm::{ShmPublisher, ShmSubscriber};
+#[cfg(feature = "std")]
+pub use slam::{
+    resample, Backend, FixedRate, GridEdge, HostClock, HostSlamProcessor, MacBackend,
+    OutlierLimits, SlamConfig, SlamIter, SlamReceiver, SlamStats, SlamStream, StartupPhase,
+    StartupSequence, StartupStep, StartupTimings, StreamEvent, TrackingStatus, VelocityEstimator,
+    CAMERAS_INACTIVE_SAMPLES,
+};
 pub use types::*;
 
 /// Result type alias for xvisio operations.
-pub type Result<T> = std::result::Result<T, XvisioError>;
+pub type Result<T> = core::result::Result<T, XvisioError>;
@@ -0,0 +1,109 @@
+//! Float functions missing from `core`.
+//!
+//! With `std` these forward to the inherent `f64` methods. Without it they fall back
+//! to small software implementations, accurate to a few ULP over the ranges the
+//! SLAM parsing needs, so `no_std` builds don't pull in a libm dependency.
+
+#[cfg(feature = "std")]
+pub(crate) fn sqrt(x: f64) -> f64 {
+    x.sqrt()
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn atan2(y: f64, x: f64) -> f64 {
+    y.atan2(x)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn asin(x: f64) -> f64 {
+    x.asin()
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) use soft::{asin, atan2, sqrt};
+
+#[cfg(any(test, not(feature = "std")))]
+mod soft {
+    use core::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    /// Newton-Raphson from a bit-level initial estimate.
+    pub(crate) fn sqrt(x: f64) -> f64 {
+        if x.is_nan() || x < 0.0 {
+            return f64::NAN;
+        }
+        if x == 0.0 || x.is_infinite() {
+            return x;
+        }
+        let mut y = f64::from_bits((x.to_bits() >> 1) + 0x1FF8_0000_0000_0000);
+        for _ in 0..6 {
+            y = 0.5 * (y + x / y);
+        }
+        y
+    }
+
+    /// atan for |x| <= 1, reduced to |t| <= tan(π/8) before the Taylor series.
+    fn atan_unit(x: f64) -> f64 {
+        let (offset, t) = if x > 0.414_213_562_373_095 {
+            (FRAC_PI_4, (x - 1.0) / (x + 1.0))
+        } else if x < -0.414_213_562_373_095 {
+            (-FRAC_PI_4, (x + 1.0) / (1.0 - x))
+        } else {
+            (0.0, x)
+        };
+        let t2 = t * t;
+        let mut term = t;
+        let mut sum = 0.0;
+        for k in 0..24 {
+            sum += term / (2 * k + 1) as f64;
+            term *= -t2;
+        }
+        offset + sum
+    }
+
+    fn atan(x: f64) -> f64 {
+        if x.abs() <= 1.0 {
+            atan_unit(x)
+        } else {
+            FRAC_PI_2.copysign(x) - atan_unit(1.0 / x)
+        }
+    }
+
+    pub(crate) fn atan2(y: f64, x: f64) -> f64 {
+        if x > 0.0 {
+            atan(y / x)
+        } else if x < 0.0 {
+            atan(y / x) + PI.copysign(y)
+        } else if y != 0.0 {
+            FRAC_PI_2.copysign(y)
+        } else {
+            0.0
+        }
+    }
+
+    pub(crate) fn asin(x: f64) -> f64 {
+        atan2(x, sqrt((1.0 - x) * (1.0 + x)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::soft;
+
+    #[test]
+    fn test_soft_matches_std() {
+        for i in -100..=100 {
+            let v = i as f64 / 100.0;
+            assert!((soft::asin(v) - v.asin()).abs() < 1e-12, "asin({})", v);
+            assert!((soft::sqrt(v.abs() * 7.0) - (v.abs() * 7.0).sqrt()).abs() < 1e-12);
+            for j in -10..=10 {
+                let x = j as f64 / 3.0;
+                assert!(
+                    (soft::atan2(v, x) - v.atan2(x)).abs() < 1e-12,
+                    "atan2({}, {})",
+                    v,
+                    x
+                );
+            }
+        }
+    }
+}
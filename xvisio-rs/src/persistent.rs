@@ -0,0 +1,209 @@
+//! Device handle that follows one XR50, by UUID, across unplug and replug.
+//!
+//! A [`PersistentStream`] watches its [`SlamStream`] for the end of the data: the
+//! reader stopping (device gone, watchdog stop) or no sample for
+//! [`SILENCE_TIMEOUT`]. It then drops the stale handles, polls enumeration for the
+//! UUID and restarts SLAM with the original [`SlamConfig`], all inside `recv`.
+
+use crate::device::{list_devices, Device};
+use crate::slam::{SlamConfig, SlamStream, StreamEvent};
+use crate::types::{SlamMode, SlamSample};
+use crate::{Result, XvisioError};
+use crossbeam_channel::{Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// How long a stream may go without samples (~950 Hz nominal) before it counts as lost.
+pub const SILENCE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Events buffered for [`PersistentStream::events`] before new ones are dropped.
+const EVENT_CAPACITY: usize = 32;
+/// Delay between enumeration polls while the device is away.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A [`Device`] remembered by UUID, re-opened whenever its stream is lost.
+pub struct PersistentDevice {
+    uuid: String,
+    device: Option<Device>,
+}
+
+impl PersistentDevice {
+    /// Follow an already opened device.
+    pub fn new(device: Device) -> Self {
+        Self {
+            uuid: device.uuid().to_string(),
+            device: Some(device),
+        }
+    }
+
+    /// Open the connected XR50 with this UUID.
+    pub fn open(uuid: &str) -> Result<Self> {
+        Ok(Self {
+            uuid: uuid.to_string(),
+            device: Some(open_uuid(uuid)?),
+        })
+    }
+
+    /// UUID of the followed device.
+    pub fn uuid(&self) -> &str {
+        &self.uuid
+    }
+
+    /// The current handle; `None` while the device is away.
+    pub fn device(&self) -> Option<&Device> {
+        self.device.as_ref()
+    }
+
+    /// Start SLAM streaming in the specified mode, see [`Device::start_slam`].
+    pub fn start_slam(self, mode: SlamMode) -> Result<PersistentStream> {
+        self.start_slam_with_config(SlamConfig::new(mode))
+    }
+
+    /// Start SLAM streaming with `config`, kept to restart the stream after a replug.
+    pub fn start_slam_with_config(mut self, config: SlamConfig) -> Result<PersistentStream> {
+        let device = match &mut self.device {
+            Some(device) => device,
+            None => self.device.insert(open_uuid(&self.uuid)?),
+        };
+        let stream = device.start_slam_with_config(&config)?;
+        let (event_sender, events) = crossbeam_channel::bounded(EVENT_CAPACITY);
+        Ok(PersistentStream {
+            device: self,
+            config,
+            stream: Some(stream),
+            last_sample: Instant::now(),
+            reconnects: 0,
+            event_sender,
+            events,
+        })
+    }
+}
+
+/// SLAM stream of a [`PersistentDevice`] that resumes after disconnects.
+///
+/// Samples missed while the device was away are simply absent; a restarted stream
+/// begins a new device clock and `sequence` count.
+pub struct PersistentStream {
+    device: PersistentDevice,
+    config: SlamConfig,
+    stream: Option<SlamStream>,
+    last_sample: Instant,
+    reconnects: u64,
+    event_sender: Sender<StreamEvent>,
+    events: Receiver<StreamEvent>,
+}
+
+impl PersistentStream {
+    /// Receive the next SLAM sample, reconnecting as often as needed (blocks until available).
+    pub fn recv(&mut self) -> Result<SlamSample> {
+        loop {
+            match self.recv_timeout(SILENCE_TIMEOUT) {
+                Err(XvisioError::Timeout) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Receive a SLAM sample with a timeout; reconnection attempts count against it.
+    ///
+    /// Errors other than [`XvisioError::Timeout`] are ones a reconnect can't fix,
+    /// e.g. [`XvisioError::Cancelled`] from the config's cancel token.
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Result<SlamSample> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline
+                .checked_duration_since(Instant::now())
+                .ok_or(XvisioError::Timeout)?;
+            let Some(stream) = &self.stream else {
+                if let Err(e) = self.reconnect() {
+                    if matches!(e, XvisioError::Cancelled) {
+                        return Err(e);
+                    }
+                    log::debug!("XR50 {} not back yet: {}", self.device.uuid, e);
+                    std::thread::sleep(POLL_INTERVAL.min(remaining));
+                }
+                continue;
+            };
+            forward_events(stream, &self.event_sender);
+            match stream.recv_timeout(remaining.min(SILENCE_TIMEOUT)) {
+                Ok(sample) => {
+                    self.last_sample = Instant::now();
+                    return Ok(sample);
+                }
+                Err(XvisioError::Timeout) if self.last_sample.elapsed() < SILENCE_TIMEOUT => {}
+                Err(e) => {
+                    log::warn!(
+                        "SLAM stream of {} lost ({}), reconnecting",
+                        self.device.uuid,
+                        e
+                    );
+                    if !forward_events(stream, &self.event_sender) {
+                        let _ = self.event_sender.try_send(StreamEvent::Disconnected);
+                    }
+                    self.stream = None;
+                    self.device.device = None;
+                }
+            }
+        }
+    }
+
+    /// Channel of [`StreamEvent`]s across reconnects: those of the current
+    /// stream, passed on while `recv` runs, plus
+    /// [`Reconnected`](StreamEvent::Reconnected). A loss without a device
+    /// disconnect, e.g. silence, is reported as `Disconnected` too.
+    pub fn events(&self) -> Receiver<StreamEvent> {
+        self.events.clone()
+    }
+
+    /// Times the stream was re-established after a loss.
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects
+    }
+
+    /// Whether a stream is currently running; false while the device is away.
+    pub fn is_connected(&self) -> bool {
+        self.stream.as_ref().is_some_and(|s| s.is_active())
+    }
+
+    /// Stop the stream, keeping the device for a later start.
+    pub fn stop(mut self) -> PersistentDevice {
+        if let Some(stream) = self.stream.take() {
+            stream.stop();
+        }
+        self.device
+    }
+
+    fn reconnect(&mut self) -> Result<()> {
+        self.config.check_cancelled()?;
+        let mut device = open_uuid(&self.device.uuid)?;
+        let stream = device.start_slam_with_config(&self.config)?;
+        self.device.device = Some(device);
+        self.stream = Some(stream);
+        self.last_sample = Instant::now();
+        self.reconnects += 1;
+        let _ = self.event_sender.try_send(StreamEvent::Reconnected);
+        log::info!(
+            "XR50 {} reconnected ({} so far)",
+            self.device.uuid,
+            self.reconnects
+        );
+        Ok(())
+    }
+}
+
+/// Pass the events queued on `stream` on to `events`; true if one was `Disconnected`.
+fn forward_events(stream: &SlamStream, events: &Sender<StreamEvent>) -> bool {
+    stream
+        .events()
+        .try_iter()
+        .fold(false, |disconnected, event| {
+            let _ = events.try_send(event);
+            disconnected || event == StreamEvent::Disconnected
+        })
+}
+
+fn open_uuid(uuid: &str) -> Result<Device> {
+    let info = list_devices()?
+        .into_iter()
+        .find(|d| d.uuid == uuid)
+        .ok_or(XvisioError::DeviceNotFound)?;
+    Device::open(&info)
+}
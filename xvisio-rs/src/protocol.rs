@@ -1,5 +1,9 @@
-use crate::types::{Features, ImuData, Pose, SlamSample};
+use crate::math::{asin, atan2, sqrt};
+use crate::types::{EulerOrder, Features, ImuData, Pose, RotationSource, SlamSample};
+use alloc::string::{String, ToString};
+#[cfg(feature = "std")]
 use std::sync::OnceLock;
+#[cfg(feature = "std")]
 use std::time::Instant;
 
 // -- USB identifiers --
@@ -7,9 +11,15 @@ pub const VID: u16 = 0x040E;
 pub const PID: u16 = 0xF408;
 pub const HID_INTERFACE: u8 = 3;
 pub const SLAM_ENDPOINT: u8 = 0x83;
+/// Vendor-defined HID usage pages span 0xFF00..=0xFFFF; the command collection lives there.
+pub const HID_USAGE_PAGE_VENDOR: u16 = 0xFF00;
 
 // -- Packet geometry --
 pub const REPORT_SIZE: usize = 63;
+/// Nominal rate of the edge SLAM stream; measured rates range ~880-980 Hz.
+pub const NOMINAL_RATE_HZ: f64 = 950.0;
+/// Bytes covering header, timestamp, translation and rotation; the rest is extended data.
+pub const POSE_SIZE: usize = 37;
 
 /// Fixed-point scale factor: 2^(-14) = 1/16384.
 pub const SCALE: f64 = 6.103515625e-05;
@@ -28,7 +38,14 @@ pub const CMD_STEREO_CAMERA_INIT: &[u8] = &[0xFE, 0x20, 0x21];
 pub const CMD_STEREO_CAMERA_START: &[u8] = &[0xFE, 0x20, 0x22];
 
 // -- SLAM packet header echo --
-pub const SLAM_HEADER: [u8; 3] = [0x01, 0xA2, 0x33];
+/// Header of the reports streamed after an edge stream start: the device-to-host
+/// prefix followed by the echo of the start command's first two bytes.
+pub const SLAM_HEADER: [u8; 3] = stream_header([CMD_EDGE_STREAM[0], CMD_EDGE_STREAM[1]]);
+
+/// Header of the reports a device streams in answer to `command`.
+pub const fn stream_header(command: [u8; 2]) -> [u8; 3] {
+    [PREFIX_DEVICE_TO_HOST, command[0], command[1]]
+}
 
 /// Build a 63-byte HID command buffer.
 /// Format: [0x02, cmd_bytes..., 0x00 padding...]
@@ -97,22 +114,167 @@ pub fn build_stereo_camera_start_cmd() -> [u8; REPORT_SIZE] {
     build_command(CMD_STEREO_CAMERA_START)
 }
 
+/// A host-to-device command, checked to fit in one report.
+///
+/// Named constructors cover the known commands; [`raw`](Self::raw) sends anything
+/// else. See [`Device::send_command`](crate::Device::send_command).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandBuilder {
+    report: [u8; REPORT_SIZE],
+    len: usize,
+    echo_len: usize,
+}
+
+impl CommandBuilder {
+    /// Arbitrary command bytes, without the 0x02 prefix. At most `REPORT_SIZE - 1`
+    /// bytes; the first two are taken as the opcode the device echoes.
+    pub fn raw(cmd: &[u8]) -> crate::Result<Self> {
+        if cmd.len() > REPORT_SIZE - 1 {
+            return Err(crate::XvisioError::CommandTooLong(cmd.len()));
+        }
+        Ok(Self::with_echo(cmd, cmd.len().min(2)))
+    }
+
+    fn with_echo(cmd: &[u8], echo_len: usize) -> Self {
+        CommandBuilder {
+            report: build_command(cmd),
+            len: cmd.len(),
+            echo_len,
+        }
+    }
+
+    pub fn uuid() -> Self {
+        Self::with_echo(CMD_UUID, CMD_UUID.len())
+    }
+
+    pub fn version() -> Self {
+        Self::with_echo(CMD_VERSION, CMD_VERSION.len())
+    }
+
+    pub fn features() -> Self {
+        Self::with_echo(CMD_FEATURES, CMD_FEATURES.len())
+    }
+
+    /// See [`build_configure_cmd_with_uvc`].
+    pub fn configure(edge: bool, uvc_mode: u8, embedded_algo: bool) -> Self {
+        CommandBuilder {
+            report: build_configure_cmd_with_uvc(edge, uvc_mode, embedded_algo),
+            len: CMD_CONFIGURE.len() + 3,
+            echo_len: CMD_CONFIGURE.len(),
+        }
+    }
+
+    /// See [`build_edge_stream_cmd_with_params`].
+    pub fn edge_stream(edge_mode: u8, rotation_enabled: bool, flipped: bool) -> Self {
+        CommandBuilder {
+            report: build_edge_stream_cmd_with_params(edge_mode, rotation_enabled, flipped),
+            len: CMD_EDGE_STREAM.len() + 3,
+            echo_len: CMD_EDGE_STREAM.len(),
+        }
+    }
+
+    pub fn stereo_camera_init() -> Self {
+        Self::with_echo(CMD_STEREO_CAMERA_INIT, CMD_STEREO_CAMERA_INIT.len())
+    }
+
+    pub fn stereo_camera_start() -> Self {
+        Self::with_echo(CMD_STEREO_CAMERA_START, CMD_STEREO_CAMERA_START.len())
+    }
+
+    /// Command bytes without the prefix.
+    pub fn bytes(&self) -> &[u8] {
+        &self.report[1..1 + self.len]
+    }
+
+    /// The full report: [0x02, command..., padding].
+    pub fn build(&self) -> [u8; REPORT_SIZE] {
+        self.report
+    }
+
+    /// The opcode bytes the device echoes in its response.
+    pub fn echo(&self) -> &[u8] {
+        &self.bytes()[..self.echo_len]
+    }
+
+    /// Split a device-to-host report answering this command.
+    pub fn response<'a>(&self, report: &'a [u8]) -> Response<'a> {
+        Response::parse(report, self.echo())
+    }
+}
+
+/// A device-to-host report split against the command it answers.
+/// Report format: [0x01, cmd_echo..., payload...]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Response<'a> {
+    /// First byte; [`PREFIX_DEVICE_TO_HOST`] when the device answered.
+    pub prefix: u8,
+    /// Bytes in the command echo position (shorter if the report is truncated).
+    pub echo: &'a [u8],
+    /// Bytes after the echo.
+    pub payload: &'a [u8],
+    /// Prefix and echo both match the command.
+    pub valid: bool,
+}
+
+impl<'a> Response<'a> {
+    pub fn parse(report: &'a [u8], expected_cmd: &[u8]) -> Self {
+        let prefix = report.first().copied().unwrap_or(0);
+        let body = report.get(1..).unwrap_or(&[]);
+        let (echo, payload) = body.split_at(expected_cmd.len().min(body.len()));
+        Response {
+            prefix,
+            echo,
+            payload,
+            valid: prefix == PREFIX_DEVICE_TO_HOST && echo == expected_cmd,
+        }
+    }
+
+    /// The device answered but echoed a different command, i.e. it rejected it.
+    /// An empty or all-zero report means no answer and is not a rejection, nor is
+    /// a SLAM packet, which a device still streaming returns instead of an answer.
+    pub fn is_rejection(&self) -> bool {
+        self.prefix == PREFIX_DEVICE_TO_HOST && !self.valid && !self.is_slam_packet()
+    }
+
+    /// The report is an edge SLAM packet rather than an answer: the device is
+    /// still streaming from an earlier session.
+    pub fn is_slam_packet(&self) -> bool {
+        self.prefix == SLAM_HEADER[0]
+            && self
+                .echo
+                .iter()
+                .chain(self.payload)
+                .take(2)
+                .eq(&SLAM_HEADER[1..])
+    }
+
+    /// `Ok` for a valid response, the matching error otherwise.
+    pub fn check(self) -> crate::Result<Self> {
+        if self.prefix != PREFIX_DEVICE_TO_HOST {
+            Err(crate::XvisioError::InvalidResponse(self.prefix))
+        } else if !self.valid {
+            Err(crate::XvisioError::CommandMismatch)
+        } else {
+            Ok(self)
+        }
+    }
+}
+
 /// Extract the command echo from a response and return the payload start offset.
 /// Response format: [0x01, cmd_echo..., payload...]
 pub fn validate_response(response: &[u8], expected_cmd: &[u8]) -> crate::Result<usize> {
-    if response.is_empty() || response[0] != PREFIX_DEVICE_TO_HOST {
-        return Err(crate::XvisioError::InvalidResponse(
-            response.first().copied().unwrap_or(0),
-        ));
-    }
-    let cmd_len = expected_cmd.len();
-    if response.len() < 1 + cmd_len {
-        return Err(crate::XvisioError::CommandMismatch);
-    }
-    if &response[1..1 + cmd_len] != expected_cmd {
-        return Err(crate::XvisioError::CommandMismatch);
+    Response::parse(response, expected_cmd)
+        .check()
+        .map(|r| 1 + r.echo.len())
+}
+
+/// Fail with [`CommandRejected`](crate::XvisioError::CommandRejected) if the ack
+/// for a fire-and-forget command (configure, edge stream) is a rejection.
+pub fn check_ack(report: &[u8], cmd: &[u8]) -> crate::Result<()> {
+    if Response::parse(report, cmd).is_rejection() {
+        return Err(crate::XvisioError::CommandRejected(cmd.to_vec()));
     }
-    Ok(1 + cmd_len)
+    Ok(())
 }
 
 /// Extract a null-terminated string from a byte slice.
@@ -121,35 +283,68 @@ pub fn extract_string(data: &[u8]) -> String {
     String::from_utf8_lossy(&data[..end]).to_string()
 }
 
+/// Extract a null-terminated identity string (UUID, version) without the lossy
+/// fallback of [`extract_string`]: anything but printable ASCII of at least
+/// `min_len` characters is a misaligned or corrupted response, returned as `Err`
+/// with the bytes up to the terminator.
+pub fn identity_string(data: &[u8], min_len: usize) -> core::result::Result<&str, &[u8]> {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    let bytes = &data[..end];
+    if bytes.len() < min_len || !bytes.iter().all(|&b| b.is_ascii_graphic() || b == b' ') {
+        return Err(bytes);
+    }
+    core::str::from_utf8(bytes).map_err(|_| bytes)
+}
+
 /// Parse features bitmap from response payload (little-endian u32).
+/// Bits without a named flag are kept, see [`Features::unknown_bits`].
 pub fn parse_features(payload: &[u8]) -> Features {
     if payload.len() < 4 {
         return Features::empty();
     }
     let bits = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
-    Features::from_bits_truncate(bits)
+    Features::from_bits_retain(bits)
 }
 
 /// Convert XR50 quaternion [w, x, y, z] to Euler angles [roll, pitch, yaw] in degrees
 /// for the Three.js frontend (YXZ order with Z-axis flip).
 ///
 /// The XR50 uses Z-forward, Three.js uses Z-backward. We apply the Z-flip to the
-/// rotation matrix (R' = T·R·T where T = diag(1,1,-1)), then extract YXZ Euler
-/// angles to match the frontend's `new THREE.Euler(pitch, yaw, roll, 'YXZ')`.
-///
-/// From the Z-flipped rotation matrix R':
-///   roll  (Euler.z) = atan2(R'[1][0], R'[1][1]) = atan2(2(xy+wz), 1 - 2(x²+z²))
-///   pitch (Euler.x) = asin(-R'[1][2])            = asin(2(yz-wx))
-///   yaw   (Euler.y) = atan2(R'[0][2], R'[2][2])  = atan2(-2(xz+wy), 1 - 2(x²+y²))
+/// rotation (R' = T·R·T where T = diag(1,1,-1), i.e. negating qx and qy), then
+/// extract YXZ Euler angles to match the frontend's `new THREE.Euler(pitch, yaw, roll, 'YXZ')`.
 pub fn quaternion_to_euler(w: f64, x: f64, y: f64, z: f64) -> [f64; 3] {
-    let roll = (2.0 * (x * y + w * z)).atan2(1.0 - 2.0 * (x * x + z * z));
-    let pitch = (2.0 * (y * z - w * x)).clamp(-1.0, 1.0).asin();
-    let yaw = (-2.0 * (x * z + w * y)).atan2(1.0 - 2.0 * (x * x + y * y));
-    [roll.to_degrees(), pitch.to_degrees(), yaw.to_degrees()]
+    let [pitch, yaw, roll] = quaternion_to_euler_order(w, -x, -y, z, EulerOrder::Yxz);
+    [roll, pitch, yaw]
+}
+
+/// Convert quaternion [w, x, y, z] to Tait-Bryan angles in degrees, returned as
+/// the angles about [X, Y, Z] whatever the order (as in `THREE.Euler(x, y, z, order)`).
+///
+/// For order ABC the rotation is R = R_A · R_B · R_C. The middle angle is
+/// clamped to ±90°; within ~0.03° of that (gimbal lock) the first and last
+/// axes coincide, so the last angle is set to 0 and the first absorbs the rotation.
+pub fn quaternion_to_euler_order(w: f64, x: f64, y: f64, z: f64, order: EulerOrder) -> [f64; 3] {
+    let m = quaternion_to_rotation(w, x, y, z);
+    let [i, j, k] = order.axes();
+    // +1 for the cyclic orders XYZ, YZX, ZXY.
+    let s = if (j + 3 - i) % 3 == 1 { 1.0 } else { -1.0 };
+
+    let sin_b = (s * m[i][k]).clamp(-1.0, 1.0);
+    let (a, c) = if sin_b.abs() < 0.9999999 {
+        (atan2(-s * m[j][k], m[k][k]), atan2(-s * m[i][j], m[i][i]))
+    } else {
+        (atan2(s * m[k][j], m[j][j]), 0.0)
+    };
+
+    let mut angles = [0.0; 3];
+    angles[i] = a.to_degrees();
+    angles[j] = asin(sin_b).to_degrees();
+    angles[k] = c.to_degrees();
+    angles
 }
 
 /// Convert quaternion [w, x, y, z] to a 3x3 rotation matrix (row-major).
-fn quaternion_to_rotation(w: f64, x: f64, y: f64, z: f64) -> [[f64; 3]; 3] {
+pub(crate) fn quaternion_to_rotation(w: f64, x: f64, y: f64, z: f64) -> [[f64; 3]; 3] {
     [
         [
             1.0 - 2.0 * (y * y + z * z),
@@ -173,7 +368,7 @@ fn quaternion_to_rotation(w: f64, x: f64, y: f64, z: f64) -> [[f64; 3]; 3] {
 fn rotation_to_quaternion(m: &[[f64; 3]; 3]) -> [f64; 4] {
     let trace = m[0][0] + m[1][1] + m[2][2];
     if trace > 0.0 {
-        let s = (trace + 1.0).sqrt() * 2.0;
+        let s = sqrt(trace + 1.0) * 2.0;
         [
             0.25 * s,
             (m[2][1] - m[1][2]) / s,
@@ -181,7 +376,7 @@ fn rotation_to_quaternion(m: &[[f64; 3]; 3]) -> [f64; 4] {
             (m[1][0] - m[0][1]) / s,
         ]
     } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
-        let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+        let s = sqrt(1.0 + m[0][0] - m[1][1] - m[2][2]) * 2.0;
         [
             (m[2][1] - m[1][2]) / s,
             0.25 * s,
@@ -189,7 +384,7 @@ fn rotation_to_quaternion(m: &[[f64; 3]; 3]) -> [f64; 4] {
             (m[0][2] + m[2][0]) / s,
         ]
     } else if m[1][1] > m[2][2] {
-        let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+        let s = sqrt(1.0 + m[1][1] - m[0][0] - m[2][2]) * 2.0;
         [
             (m[0][2] - m[2][0]) / s,
             (m[0][1] + m[1][0]) / s,
@@ -197,7 +392,7 @@ fn rotation_to_quaternion(m: &[[f64; 3]; 3]) -> [f64; 4] {
             (m[1][2] + m[2][1]) / s,
         ]
     } else {
-        let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+        let s = sqrt(1.0 + m[2][2] - m[0][0] - m[1][1]) * 2.0;
         [
             (m[1][0] - m[0][1]) / s,
             (m[0][2] + m[2][0]) / s,
@@ -207,12 +402,26 @@ fn rotation_to_quaternion(m: &[[f64; 3]; 3]) -> [f64; 4] {
     }
 }
 
+/// Little-endian int16 at `offset`, scaled by [`SCALE`]. `None` past the end of `data`.
+#[inline]
+pub fn read_fixed_i16(data: &[u8], offset: usize) -> Option<f64> {
+    let b = data.get(offset..offset.checked_add(2)?)?;
+    Some(i16::from_le_bytes([b[0], b[1]]) as f64 * SCALE)
+}
+
+/// Little-endian int32 at `offset`, scaled by [`SCALE`]. `None` past the end of `data`.
+#[inline]
+pub fn read_fixed_i32(data: &[u8], offset: usize) -> Option<f64> {
+    let b = data.get(offset..offset.checked_add(4)?)?;
+    Some(i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64 * SCALE)
+}
+
 fn parse_rotation_matrix(data: &[u8]) -> [[f64; 3]; 3] {
     let mut rot = [[0.0f64; 3]; 3];
     let mut idx = 19usize;
     for row in &mut rot {
         for cell in row {
-            *cell = i16::from_le_bytes([data[idx], data[idx + 1]]) as f64 * SCALE;
+            *cell = read_fixed_i16(data, idx).unwrap_or(0.0);
             idx += 2;
         }
     }
@@ -220,12 +429,14 @@ fn parse_rotation_matrix(data: &[u8]) -> [[f64; 3]; 3] {
 }
 
 #[derive(Clone, Copy)]
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
 enum RotationParseMode {
     Auto,
     Matrix,
     Quaternion,
 }
 
+#[cfg(feature = "std")]
 fn rotation_parse_mode() -> RotationParseMode {
     static MODE: OnceLock<RotationParseMode> = OnceLock::new();
     *MODE.get_or_init(|| {
@@ -241,6 +452,12 @@ fn rotation_parse_mode() -> RotationParseMode {
     })
 }
 
+/// Without `std` there is no environment to read `XVISIO_ROTATION_PARSE` from.
+#[cfg(not(feature = "std"))]
+fn rotation_parse_mode() -> RotationParseMode {
+    RotationParseMode::Auto
+}
+
 /// XR50 packets are usually matrix-formatted at bytes [19..36].
 /// Keep a fallback for quaternion-formatted variants.
 fn is_plausible_rotation_matrix(m: &[[f64; 3]; 3]) -> bool {
@@ -258,70 +475,120 @@ fn is_plausible_rotation_matrix(m: &[[f64; 3]; 3]) -> bool {
 /// Convert a 3x3 rotation matrix to Euler angles [roll, pitch, yaw] in degrees.
 /// Kept for backwards compatibility. Prefer quaternion_to_euler for SLAM data.
 pub fn rotation_to_euler(m: &[[f64; 3]; 3]) -> [f64; 3] {
-    let pitch = (-m[2][0]).asin();
-    let (roll, yaw) = if pitch.cos().abs() > 1e-6 {
-        let roll = m[2][1].atan2(m[2][2]);
-        let yaw = m[1][0].atan2(m[0][0]);
+    let pitch = asin(-m[2][0]);
+    let (roll, yaw) = if sqrt(1.0 - m[2][0] * m[2][0]) > 1e-6 {
+        let roll = atan2(m[2][1], m[2][2]);
+        let yaw = atan2(m[1][0], m[0][0]);
         (roll, yaw)
     } else {
-        let roll = m[0][1].atan2(m[1][1]);
+        let roll = atan2(m[0][1], m[1][1]);
         (roll, 0.0)
     };
     [roll.to_degrees(), pitch.to_degrees(), yaw.to_degrees()]
 }
 
-/// Parse a 63-byte SLAM packet into a SlamSample.
+/// A buffer read from the SLAM interrupt pipe, normalized to start with the report ID.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PacketKind<'a> {
+    /// Report ID included (hidapi, some libusb configurations).
+    Slam(&'a [u8]),
+    /// Raw interrupt transfer starting at the command echo; the report ID was re-inserted.
+    EchoPrefixed(&'a [u8]),
+    /// SLAM header present but fewer than [`POSE_SIZE`] bytes, too short to parse.
+    /// Normalized to start with the report ID like the other SLAM kinds.
+    Short(&'a [u8]),
+    /// Anything else, passed through unchanged.
+    Unexpected(&'a [u8]),
+}
+
+/// Classify the first `len` bytes of `buf`.
 ///
-/// Packet layout:
-/// - `[0]`: 0x01 (response indicator)
-/// - `[1..2]`: 0xA2, 0x33 (command echo)
-/// - `[3..6]`: uint32 LE timestamp (microseconds)
-/// - `[7..18]`: 3x int32 LE translation (scaled by 2^-14)
-/// - `[19..36]`: rotation payload:
-///   - Common XR50 format: 9x int16 LE 3x3 rotation matrix (row-major)
-///   - Alternate format: quaternion [w, x, y, z] in first 8 bytes
-/// - `[37..62]`: extended data (IMU, confidence, padding)
-pub fn parse_slam_packet(data: &[u8], epoch: Instant) -> Option<SlamSample> {
-    if data.len() < REPORT_SIZE {
-        return None;
-    }
+/// Framing is judged per packet, so 62-byte transfers without the report ID and
+/// 63- or 64-byte reports with it all come back starting at the report ID, where
+/// the parse offsets apply; bytes past [`REPORT_SIZE`] are carried but unused.
+/// Echo-prefixed packets are shifted right by one in place to make room for the
+/// report ID; if `buf` is full the last byte is dropped, so a 63-byte report
+/// needs a buffer of at least 64.
+pub fn classify_packet(buf: &mut [u8], len: usize) -> PacketKind<'_> {
+    classify_packet_with(buf, len, SLAM_HEADER)
+}
 
-    // Validate header
-    if data[0] != SLAM_HEADER[0] || data[1] != SLAM_HEADER[1] || data[2] != SLAM_HEADER[2] {
-        return None;
+/// [`classify_packet`] for a stream whose reports start with `header` instead of
+/// [`SLAM_HEADER`]. Matching reports come back with `header` rewritten to
+/// `SLAM_HEADER`, so the parse functions accept them.
+pub fn classify_packet_with(buf: &mut [u8], len: usize, header: [u8; 3]) -> PacketKind<'_> {
+    let len = len.min(buf.len());
+    if len >= 2 && buf[..2] == header[1..] {
+        let total = (len + 1).min(buf.len());
+        buf.copy_within(0..total - 1, 1);
+        buf[..3].copy_from_slice(&SLAM_HEADER);
+        if total < POSE_SIZE {
+            return PacketKind::Short(&buf[..total]);
+        }
+        return PacketKind::EchoPrefixed(&buf[..total]);
+    }
+    let matches = buf[..len].starts_with(&header);
+    if matches {
+        buf[..3].copy_from_slice(&SLAM_HEADER);
     }
+    if len >= REPORT_SIZE && buf[0] == header[0] {
+        return PacketKind::Slam(&buf[..len]);
+    }
+    if len < POSE_SIZE && matches {
+        return PacketKind::Short(&buf[..len]);
+    }
+    PacketKind::Unexpected(&buf[..len])
+}
 
+/// Parse a 63-byte SLAM packet into a SlamSample, stamping `host_timestamp_s`
+/// as seconds since `epoch`.
+#[cfg(feature = "std")]
+pub fn parse_slam_packet(data: &[u8], epoch: Instant) -> Option<SlamSample> {
     let host_timestamp_s = epoch.elapsed().as_secs_f64();
+    let mut sample = parse_sample(data)?;
+    sample.pose.host_timestamp_s = host_timestamp_s;
+    Some(sample)
+}
+
+/// Parse only the pose from a SLAM packet, skipping the IMU and extended data.
+/// Clock-free, available without `std`; `host_timestamp_s` is left at 0.
+/// See [`parse_sample`] for the layout and when `None` is returned.
+pub fn parse_pose(data: &[u8]) -> Option<Pose> {
+    if data.len() < POSE_SIZE || data[..3] != SLAM_HEADER {
+        return None;
+    }
 
     // Timestamp (uint32 LE)
     let timestamp_us = u32::from_le_bytes([data[3], data[4], data[5], data[6]]) as u64;
 
     // Translation (3x int32 LE, scaled)
-    let tx = i32::from_le_bytes([data[7], data[8], data[9], data[10]]) as f64 * SCALE;
-    let ty = i32::from_le_bytes([data[11], data[12], data[13], data[14]]) as f64 * SCALE;
-    let tz = i32::from_le_bytes([data[15], data[16], data[17], data[18]]) as f64 * SCALE;
+    let tx = read_fixed_i32(data, 7)?;
+    let ty = read_fixed_i32(data, 11)?;
+    let tz = read_fixed_i32(data, 15)?;
 
     let parse_quaternion = || {
-        let w = i16::from_le_bytes([data[19], data[20]]) as f64 * SCALE;
-        let x = i16::from_le_bytes([data[21], data[22]]) as f64 * SCALE;
-        let y = i16::from_le_bytes([data[23], data[24]]) as f64 * SCALE;
-        let z = i16::from_le_bytes([data[25], data[26]]) as f64 * SCALE;
-        (quaternion_to_rotation(w, x, y, z), w, x, y, z)
+        let fixed = |offset| read_fixed_i16(data, offset).unwrap_or(0.0);
+        let (w, x, y, z) = (fixed(19), fixed(21), fixed(23), fixed(25));
+        let q = (w, x, y, z);
+        (
+            quaternion_to_rotation(w, x, y, z),
+            q,
+            RotationSource::Quaternion,
+        )
+    };
+    let from_matrix = |m: [[f64; 3]; 3]| {
+        let [w, x, y, z] = rotation_to_quaternion(&m);
+        (m, (w, x, y, z), RotationSource::Matrix)
     };
 
-    let (rotation, qw, qx, qy, qz) = match rotation_parse_mode() {
+    let (rotation, (qw, qx, qy, qz), rotation_source) = match rotation_parse_mode() {
         RotationParseMode::Quaternion => parse_quaternion(),
-        RotationParseMode::Matrix => {
-            let m = parse_rotation_matrix(data);
-            let [w, x, y, z] = rotation_to_quaternion(&m);
-            (m, w, x, y, z)
-        }
+        RotationParseMode::Matrix => from_matrix(parse_rotation_matrix(data)),
         RotationParseMode::Auto => {
             // Rotation payload at bytes [19..36] is usually a 3x3 matrix in XR50 packets.
             let matrix_candidate = parse_rotation_matrix(data);
             if is_plausible_rotation_matrix(&matrix_candidate) {
-                let [w, x, y, z] = rotation_to_quaternion(&matrix_candidate);
-                (matrix_candidate, w, x, y, z)
+                from_matrix(matrix_candidate)
             } else {
                 parse_quaternion()
             }
@@ -332,39 +599,87 @@ pub fn parse_slam_packet(data: &[u8], epoch: Instant) -> Option<SlamSample> {
     let quaternion = [qx, qy, qz, qw];
     let euler_deg = quaternion_to_euler(qw, qx, qy, qz);
 
-    // Extended data [37..62]
+    // Confidence from bytes [57..58] scaled; see `Pose::confidence` for caveats
+    let confidence_raw = data
+        .get(57..59)
+        .map_or(0, |b| i16::from_le_bytes([b[0], b[1]]));
+    let confidence = read_fixed_i16(data, 57).map_or(0.0, |c| c.clamp(0.0, 1.0));
+
+    let pose = Pose {
+        translation: [tx, ty, tz],
+        rotation,
+        quaternion,
+        timestamp_us,
+        host_timestamp_s: 0.0,
+        confidence,
+        euler_deg,
+        confidence_raw,
+        rotation_source,
+    };
+    pose.is_finite().then_some(pose)
+}
+
+/// [`parse_pose`] into a caller-provided `Pose`, for hot loops and benchmarks.
+/// Returns false, leaving `out` untouched, where `parse_pose` returns `None`.
+pub fn parse_pose_into(data: &[u8], out: &mut Pose) -> bool {
+    match parse_pose(data) {
+        Some(pose) => {
+            *out = pose;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Parse a 63-byte SLAM packet into a SlamSample with `host_timestamp_s` left at 0.
+///
+/// Packet layout:
+/// - `[0]`: 0x01 (response indicator)
+/// - `[1..2]`: 0xA2, 0x33 (command echo)
+/// - `[3..6]`: uint32 LE timestamp (microseconds)
+/// - `[7..18]`: 3x int32 LE translation (scaled by 2^-14)
+/// - `[19..36]`: rotation payload:
+///   - Common XR50 format: 9x int16 LE 3x3 rotation matrix (row-major)
+///   - Alternate format: quaternion [w, x, y, z] in first 8 bytes
+/// - `[37..62]`: extended data (IMU, confidence, padding)
+///
+/// Only the first [`POSE_SIZE`] bytes are required. Extended fields past the end of a
+/// truncated packet come back as `imu: None`, `confidence: 0.0` and zeroed `raw_extended`.
+///
+/// Returns `None` for a pose that isn't finite (see [`Pose::is_finite`]), e.g. a
+/// degenerate matrix forced through `XVISIO_ROTATION_PARSE=matrix`, so the reader
+/// forwards such packets unparsed instead of as samples.
+pub fn parse_sample(data: &[u8]) -> Option<SlamSample> {
+    let pose = parse_pose(data)?;
+
+    // Extended data [37..62], zero-padded if the packet is truncated
+    let extended = &data[POSE_SIZE..data.len().min(REPORT_SIZE)];
     let mut raw_extended = [0u8; 26];
-    raw_extended.copy_from_slice(&data[37..63]);
+    raw_extended[..extended.len()].copy_from_slice(extended);
 
     // Parse IMU data (hypothesis from protocol analysis)
-    let accel_x = i16::from_le_bytes([data[37], data[38]]) as f64 * SCALE;
-    let accel_y = i16::from_le_bytes([data[39], data[40]]) as f64 * SCALE;
-    let accel_z = i16::from_le_bytes([data[41], data[42]]) as f64 * SCALE;
-    let gyro_x = i16::from_le_bytes([data[43], data[44]]) as f64 * SCALE;
-    let gyro_y = i16::from_le_bytes([data[45], data[46]]) as f64 * SCALE;
-    let gyro_z = i16::from_le_bytes([data[47], data[48]]) as f64 * SCALE;
-
-    let imu = Some(ImuData {
-        accelerometer: [accel_x, accel_y, accel_z],
-        gyroscope: [gyro_x, gyro_y, gyro_z],
+    let imu = (data.len() >= 49).then(|| {
+        let axis = |i| read_fixed_i16(data, i).unwrap_or(0.0);
+        let clipped = |i: usize| {
+            matches!(
+                i16::from_le_bytes([data[i], data[i + 1]]),
+                i16::MIN | i16::MAX
+            )
+        };
+        ImuData {
+            accelerometer: [axis(37), axis(39), axis(41)],
+            gyroscope: [axis(43), axis(45), axis(47)],
+            accel_saturated: [clipped(37), clipped(39), clipped(41)],
+            gyro_saturated: [clipped(43), clipped(45), clipped(47)],
+        }
     });
 
-    // Confidence from bytes [57..58] scaled
-    let confidence_raw = i16::from_le_bytes([data[57], data[58]]) as f64 * SCALE;
-    let confidence = confidence_raw.clamp(0.0, 1.0);
-
     Some(SlamSample {
-        pose: Pose {
-            translation: [tx, ty, tz],
-            rotation,
-            quaternion,
-            timestamp_us,
-            host_timestamp_s,
-            confidence,
-            euler_deg,
-        },
+        pose,
         imu,
         raw_extended,
+        sequence: 0,
+        host_elapsed: None,
     })
 }
 
@@ -380,6 +695,27 @@ mod tests {
         assert_eq!(buf[5], 0);
     }
 
+    #[test]
+    fn test_command_builder() {
+        assert_eq!(CommandBuilder::uuid().build(), build_command(CMD_UUID));
+        let configure = CommandBuilder::configure(true, 1, false);
+        assert_eq!(configure.bytes(), &[0x19, 0x95, 0x01, 0x01, 0x00]);
+        assert_eq!(
+            configure.build(),
+            build_configure_cmd_with_uvc(true, 1, false)
+        );
+        assert!(configure.response(&[0x01, 0x19, 0x95, 0x00]).valid);
+
+        let raw = CommandBuilder::raw(&[0xAB, 0xCD, 0x01]).unwrap();
+        assert_eq!(raw.build()[..4], [0x02, 0xAB, 0xCD, 0x01]);
+        assert!(raw.response(&[0x01, 0xAB, 0xCD, 0x07]).valid);
+        assert!(CommandBuilder::raw(&[0; REPORT_SIZE - 1]).is_ok());
+        assert!(matches!(
+            CommandBuilder::raw(&[0; REPORT_SIZE]),
+            Err(crate::XvisioError::CommandTooLong(REPORT_SIZE))
+        ));
+    }
+
     #[test]
     fn test_validate_response() {
         let mut resp = [0u8; 63];
@@ -390,6 +726,32 @@ mod tests {
         assert_eq!(offset, 5);
     }
 
+    #[test]
+    fn test_parse_features_keeps_unknown_bits() {
+        let features = parse_features(&[0x05, 0x80, 0x00, 0x00]);
+        assert_eq!(features.bits(), 0x8005);
+        assert!(features.contains(Features::EDGE_MODE | Features::STEREO));
+        assert_eq!(features.unknown_bits(), 0x8000);
+    }
+
+    #[test]
+    fn test_check_ack() {
+        let mut ack = [0u8; 63];
+        assert!(check_ack(&ack, CMD_CONFIGURE).is_ok());
+        assert!(check_ack(&[], CMD_CONFIGURE).is_ok());
+        ack[0] = PREFIX_DEVICE_TO_HOST;
+        ack[1..3].copy_from_slice(CMD_CONFIGURE);
+        assert!(check_ack(&ack, CMD_CONFIGURE).is_ok());
+        ack[2] = 0x00;
+        assert!(matches!(
+            check_ack(&ack, CMD_CONFIGURE),
+            Err(crate::XvisioError::CommandRejected(_))
+        ));
+        // A still-active stream answers with SLAM packets; that's not a rejection.
+        ack[..3].copy_from_slice(&SLAM_HEADER);
+        assert!(check_ack(&ack, CMD_CONFIGURE).is_ok());
+    }
+
     #[test]
     fn test_quaternion_to_euler_identity() {
         let euler = quaternion_to_euler(1.0, 0.0, 0.0, 0.0);
@@ -398,6 +760,60 @@ mod tests {
         assert!(euler[2].abs() < 1e-10); // yaw
     }
 
+    /// Quaternion [w, x, y, z] for `deg` degrees about `axis`.
+    fn axis_quat(axis: usize, deg: f64) -> [f64; 4] {
+        let half = deg.to_radians() / 2.0;
+        let mut q = [half.cos(), 0.0, 0.0, 0.0];
+        q[axis + 1] = half.sin();
+        q
+    }
+
+    fn quat_mul(a: [f64; 4], b: [f64; 4]) -> [f64; 4] {
+        [
+            a[0] * b[0] - a[1] * b[1] - a[2] * b[2] - a[3] * b[3],
+            a[0] * b[1] + a[1] * b[0] + a[2] * b[3] - a[3] * b[2],
+            a[0] * b[2] - a[1] * b[3] + a[2] * b[0] + a[3] * b[1],
+            a[0] * b[3] + a[1] * b[2] - a[2] * b[1] + a[3] * b[0],
+        ]
+    }
+
+    #[test]
+    fn test_quaternion_to_euler_order_round_trip() {
+        let orders = [
+            EulerOrder::Xyz,
+            EulerOrder::Xzy,
+            EulerOrder::Yxz,
+            EulerOrder::Yzx,
+            EulerOrder::Zxy,
+            EulerOrder::Zyx,
+        ];
+        let angles = [20.0, -35.0, 60.0];
+        for order in orders {
+            let [i, j, k] = order.axes();
+            let q = quat_mul(
+                quat_mul(axis_quat(i, angles[i]), axis_quat(j, angles[j])),
+                axis_quat(k, angles[k]),
+            );
+            let euler = quaternion_to_euler_order(q[0], q[1], q[2], q[3], order);
+            for (got, want) in euler.iter().zip(angles) {
+                assert!((got - want).abs() < 1e-9, "{:?}: {:?}", order, euler);
+            }
+        }
+    }
+
+    #[test]
+    fn test_quaternion_to_euler_order_gimbal_lock() {
+        // XYZ with Y = 90°: X and Z collapse onto one axis, reported on X.
+        let q = quat_mul(
+            quat_mul(axis_quat(0, 30.0), axis_quat(1, 90.0)),
+            axis_quat(2, 0.0),
+        );
+        let euler = quaternion_to_euler_order(q[0], q[1], q[2], q[3], EulerOrder::Xyz);
+        assert!((euler[0] - 30.0).abs() < 1e-6);
+        assert!((euler[1] - 90.0).abs() < 1e-6);
+        assert_eq!(euler[2], 0.0);
+    }
+
     #[test]
     fn test_quaternion_to_rotation_identity() {
         let m = quaternion_to_rotation(1.0, 0.0, 0.0, 0.0);
@@ -407,6 +823,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_parse_slam_packet() {
         // Example packet from PROTOCOL.md
         let data: [u8; 63] = [
@@ -423,18 +840,177 @@ mod tests {
         // Timestamp = 1596313963 µs
         assert_eq!(sample.pose.timestamp_us, 1596313963);
 
-        // Translation should be approximately [0.0210, 0.0018, 0.0275]
-        assert!((sample.pose.translation[0] - 0.0210).abs() < 0.001);
-        assert!((sample.pose.translation[1] - 0.0018).abs() < 0.001);
-        assert!((sample.pose.translation[2] - 0.0275).abs() < 0.001);
+        // Exact little-endian decodes; any other byte order gives different
+        // integers. Translation is ~[0.0210, 0.0018, 0.0275] m.
+        assert_eq!(
+            sample.pose.translation,
+            [344.0 * SCALE, 30.0 * SCALE, 451.0 * SCALE]
+        );
 
         // Rotation matrix payload should be decoded and remain normalized.
-        assert!((sample.pose.rotation[0][0] - (-0.994)).abs() < 0.01);
+        assert_eq!(sample.pose.rotation_source, RotationSource::Matrix);
+        let raw = [
+            [-16286.0, 826.0, 1581.0],
+            [-678.0, -16298.0, 1523.0],
+            [1650.0, 1449.0, 16236.0],
+        ];
+        assert_eq!(sample.pose.rotation, raw.map(|row| row.map(|r| r * SCALE)));
         let qn = (sample.pose.quaternion[0] * sample.pose.quaternion[0]
             + sample.pose.quaternion[1] * sample.pose.quaternion[1]
             + sample.pose.quaternion[2] * sample.pose.quaternion[2]
             + sample.pose.quaternion[3] * sample.pose.quaternion[3])
             .sqrt();
         assert!((qn - 1.0).abs() < 0.05);
+
+        // Bytes [57..58] = 0x412b = 16683, clamped from ~1.018
+        assert_eq!(sample.pose.confidence_raw, 16683);
+        assert_eq!(sample.pose.confidence, 1.0);
+    }
+
+    #[test]
+    fn test_read_fixed() {
+        // Translation x and the status word of the PROTOCOL.md example packet.
+        let data = [0x58, 0x01, 0x00, 0x00, 0x2b, 0x41];
+        assert_eq!(read_fixed_i32(&data, 0), Some(344.0 * SCALE));
+        assert_eq!(read_fixed_i16(&data, 4), Some(16683.0 * SCALE));
+        assert_eq!(read_fixed_i16(&[0x00, 0x80], 0), Some(-2.0));
+        assert_eq!(read_fixed_i16(&data, 5), None);
+        assert_eq!(read_fixed_i32(&data, 3), None);
+        assert_eq!(read_fixed_i16(&data, usize::MAX), None);
+    }
+
+    #[test]
+    fn test_report_size_variants_parse() {
+        let mut report = [0u8; 65];
+        report[..3].copy_from_slice(&SLAM_HEADER);
+        report[3..7].copy_from_slice(&77u32.to_le_bytes());
+        // With report ID: 63 and 64 bytes. Without: 62 and 63 bytes.
+        for (skip, len) in [(0, 63), (0, 64), (1, 62), (1, 63)] {
+            let mut buf = [0u8; 65];
+            buf[..len].copy_from_slice(&report[skip..skip + len]);
+            let (PacketKind::Slam(data) | PacketKind::EchoPrefixed(data)) =
+                classify_packet(&mut buf, len)
+            else {
+                panic!("{}-byte packet not recognized", len);
+            };
+            assert_eq!(parse_pose(data).unwrap().timestamp_us, 77);
+        }
+    }
+
+    #[test]
+    fn test_parse_truncated_packet() {
+        let mut data = [0u8; 40];
+        data[..3].copy_from_slice(&SLAM_HEADER);
+        data[3..7].copy_from_slice(&42u32.to_le_bytes());
+        data[37..40].copy_from_slice(&[0xAA, 0xBB, 0xCC]);
+
+        let sample = parse_sample(&data).unwrap();
+        assert_eq!(sample.pose.timestamp_us, 42);
+        assert!(sample.imu.is_none());
+        assert_eq!(sample.pose.confidence, 0.0);
+        assert_eq!(&sample.raw_extended[..4], &[0xAA, 0xBB, 0xCC, 0x00]);
+        assert!(parse_sample(&data[..POSE_SIZE - 1]).is_none());
+    }
+
+    #[test]
+    fn test_parse_imu_saturation() {
+        let mut data = [0u8; 63];
+        data[..3].copy_from_slice(&SLAM_HEADER);
+        data[37..39].copy_from_slice(&i16::MIN.to_le_bytes());
+        data[41..43].copy_from_slice(&16384i16.to_le_bytes());
+        data[47..49].copy_from_slice(&i16::MAX.to_le_bytes());
+
+        let imu = parse_sample(&data).unwrap().imu.unwrap();
+        assert_eq!(imu.accelerometer[0], -2.0);
+        assert_eq!(imu.accel_saturated, [true, false, false]);
+        assert_eq!(imu.gyro_saturated, [false, false, true]);
+        assert!(imu.is_saturated());
+    }
+
+    #[test]
+    fn test_parse_pose_matches_parse_slam_packet() {
+        let mut data = [0u8; 63];
+        data[..3].copy_from_slice(&SLAM_HEADER);
+        data[3..7].copy_from_slice(&1234u32.to_le_bytes());
+        data[7..11].copy_from_slice(&16384i32.to_le_bytes());
+        for i in 0..3 {
+            data[19 + 8 * i..21 + 8 * i].copy_from_slice(&16384i16.to_le_bytes());
+        }
+
+        let pose = parse_pose(&data).unwrap();
+        #[cfg(feature = "std")]
+        {
+            let sample = parse_slam_packet(&data, Instant::now()).unwrap();
+            assert_eq!(pose.timestamp_us, sample.pose.timestamp_us);
+            assert_eq!(pose.translation, sample.pose.translation);
+            assert_eq!(pose.quaternion, sample.pose.quaternion);
+        }
+        assert_eq!(pose.host_timestamp_s, 0.0);
+        assert!((pose.translation[0] - 1.0).abs() < 1e-12);
+
+        let mut into = Pose::new([0.0; 3], [0.0, 0.0, 0.0, 1.0]);
+        assert!(parse_pose_into(&data, &mut into));
+        assert_eq!(into.timestamp_us, 1234);
+        assert!(!parse_pose_into(&data[..POSE_SIZE - 1], &mut into));
+        assert_eq!(into.translation, pose.translation);
+    }
+
+    #[test]
+    fn test_classify_packet() {
+        let mut buf = [0u8; 64];
+        buf[..3].copy_from_slice(&SLAM_HEADER);
+        buf[62] = 0x7F;
+        assert!(matches!(classify_packet(&mut buf, 63), PacketKind::Slam(d) if d.len() == 63));
+
+        // Interrupt transfer without report ID: 62 bytes become a full 63-byte report.
+        let mut buf = [0u8; 64];
+        buf[..2].copy_from_slice(&SLAM_HEADER[1..]);
+        buf[61] = 0x7F;
+        let PacketKind::EchoPrefixed(data) = classify_packet(&mut buf, 62) else {
+            panic!("expected echo-prefixed packet");
+        };
+        assert_eq!(data.len(), REPORT_SIZE);
+        assert_eq!(&data[..3], &SLAM_HEADER);
+        assert_eq!(data[62], 0x7F);
+
+        // A full buffer drops its last byte instead of overflowing.
+        let mut buf = [0xA2u8; 64];
+        buf[1] = 0x33;
+        assert!(
+            matches!(classify_packet(&mut buf, 64), PacketKind::EchoPrefixed(d) if d.len() == 64)
+        );
+
+        // Runts with a valid header, in both framings.
+        let mut buf = [0u8; 64];
+        buf[..3].copy_from_slice(&SLAM_HEADER);
+        assert!(matches!(classify_packet(&mut buf, 10), PacketKind::Short(d) if d.len() == 10));
+        let mut buf = [0u8; 64];
+        buf[..2].copy_from_slice(&SLAM_HEADER[1..]);
+        assert!(matches!(classify_packet(&mut buf, 30), PacketKind::Short(d) if d.len() == 31));
+
+        let mut buf = [0u8; 64];
+        buf[0] = SLAM_HEADER[0];
+        assert!(
+            matches!(classify_packet(&mut buf, 10), PacketKind::Unexpected(d) if d.len() == 10)
+        );
+        assert!(matches!(
+            classify_packet(&mut buf, 0),
+            PacketKind::Unexpected(&[])
+        ));
+
+        // A variant echo is recognized and normalized for the parsers.
+        let variant = stream_header([0xA3, 0x34]);
+        let mut buf = [0u8; 64];
+        buf[..2].copy_from_slice(&variant[1..]);
+        let PacketKind::EchoPrefixed(data) = classify_packet_with(&mut buf, 62, variant) else {
+            panic!("expected echo-prefixed packet");
+        };
+        assert!(parse_pose(data).is_some());
+        let mut buf = [0u8; 64];
+        buf[..2].copy_from_slice(&variant[1..]);
+        assert!(matches!(
+            classify_packet(&mut buf, 62),
+            PacketKind::Unexpected(_)
+        ));
     }
 }
@@ -0,0 +1,134 @@
+//! Scheduling of the SLAM reader thread: priority and CPU affinity per platform.
+
+use std::io;
+
+/// Scheduling priority of the SLAM reader thread, see
+/// [`SlamConfig::thread_priority`](crate::SlamConfig::thread_priority).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Priority {
+    /// Leave the thread at the OS default.
+    #[default]
+    Normal,
+    /// Above normal without realtime scheduling: nice -10 on Linux, the top
+    /// `SCHED_OTHER` priority on other Unixes, `THREAD_PRIORITY_HIGHEST` on Windows.
+    High,
+    /// Realtime: mid-range `SCHED_FIFO` on Unix, `THREAD_PRIORITY_TIME_CRITICAL`
+    /// on Windows. On Linux this needs `CAP_SYS_NICE` or an `rtprio` limit.
+    Realtime,
+}
+
+/// Apply `priority` and `core` to the calling thread. Failures (missing
+/// privileges, unsupported platform) are logged and otherwise ignored, so the
+/// stream still runs at default scheduling.
+pub(crate) fn configure_current_thread(priority: Priority, core: Option<usize>) {
+    if priority != Priority::Normal {
+        if let Err(e) = set_priority(priority) {
+            log::warn!("SLAM thread: {:?} priority not applied: {}", priority, e);
+        }
+    }
+    if let Some(core) = core {
+        if let Err(e) = set_affinity(core) {
+            log::warn!("SLAM thread: pinning to core {} failed: {}", core, e);
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn set_priority(priority: Priority) -> io::Result<()> {
+    match priority {
+        Priority::Normal => Ok(()),
+        // Linux applies nice values per thread, so this leaves the caller alone.
+        Priority::High => {
+            let tid = unsafe { libc::gettid() } as libc::id_t;
+            match unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, -10) } {
+                0 => Ok(()),
+                _ => Err(io::Error::last_os_error()),
+            }
+        }
+        Priority::Realtime => set_sched_policy(libc::SCHED_FIFO, None),
+    }
+}
+
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+fn set_priority(priority: Priority) -> io::Result<()> {
+    match priority {
+        Priority::Normal => Ok(()),
+        Priority::High => set_sched_policy(
+            libc::SCHED_OTHER,
+            Some(unsafe { libc::sched_get_priority_max(libc::SCHED_OTHER) }),
+        ),
+        Priority::Realtime => set_sched_policy(libc::SCHED_FIFO, None),
+    }
+}
+
+/// Switch the calling thread to `policy` at `level`, or mid-range if `None`.
+#[cfg(unix)]
+fn set_sched_policy(policy: libc::c_int, level: Option<libc::c_int>) -> io::Result<()> {
+    let level = level.unwrap_or_else(|| unsafe {
+        let (min, max) = (
+            libc::sched_get_priority_min(policy),
+            libc::sched_get_priority_max(policy),
+        );
+        min + (max - min) / 2
+    });
+    let mut param: libc::sched_param = unsafe { std::mem::zeroed() };
+    param.sched_priority = level;
+    match unsafe { libc::pthread_setschedparam(libc::pthread_self(), policy, &param) } {
+        0 => Ok(()),
+        rc => Err(io::Error::from_raw_os_error(rc)),
+    }
+}
+
+#[cfg(windows)]
+fn set_priority(priority: Priority) -> io::Result<()> {
+    use windows_sys::Win32::System::Threading::{
+        GetCurrentThread, SetThreadPriority, THREAD_PRIORITY_HIGHEST, THREAD_PRIORITY_TIME_CRITICAL,
+    };
+    let level = match priority {
+        Priority::Normal => return Ok(()),
+        Priority::High => THREAD_PRIORITY_HIGHEST,
+        Priority::Realtime => THREAD_PRIORITY_TIME_CRITICAL,
+    };
+    match unsafe { SetThreadPriority(GetCurrentThread(), level) } {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn set_priority(_priority: Priority) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn set_affinity(core: usize) -> io::Result<()> {
+    if core >= libc::CPU_SETSIZE as usize {
+        return Err(io::ErrorKind::InvalidInput.into());
+    }
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        match libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+}
+
+#[cfg(windows)]
+fn set_affinity(core: usize) -> io::Result<()> {
+    use windows_sys::Win32::System::Threading::{GetCurrentThread, SetThreadAffinityMask};
+    if core >= usize::BITS as usize {
+        return Err(io::ErrorKind::InvalidInput.into());
+    }
+    match unsafe { SetThreadAffinityMask(GetCurrentThread(), 1 << core) } {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// macOS has no hard thread affinity, only hints.
+#[cfg(not(any(target_os = "linux", target_os = "android", windows)))]
+fn set_affinity(_core: usize) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
@@ -0,0 +1,324 @@
+//! Shared-memory pose ring for multi-process setups, see
+//! [`SlamConfig::publish_shm`](crate::SlamConfig::publish_shm).
+//!
+//! One process publishes into a named POSIX shared-memory segment; any number of
+//! others map it read-only with [`ShmSubscriber`]. Each slot is guarded by a
+//! sequence number (a seqlock), so the publisher never waits for readers: a
+//! reader that falls a whole ring behind skips ahead and counts what it missed.
+//!
+//! Layout, in native-endian 64-bit words: `magic, version, capacity, published`,
+//! then `capacity` slots of a sequence word and 23 pose words.
+//! Pose `k` lives in slot `k % capacity` while its sequence reads `2k + 2`; an
+//! odd sequence marks a slot being written.
+
+use crate::types::{Pose, RotationSource};
+use crate::Result;
+use std::ffi::CString;
+use std::io;
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+const MAGIC: u64 = u64::from_le_bytes(*b"XVSHMPOS");
+const VERSION: u64 = 1;
+const HEADER_WORDS: usize = 4;
+const PUBLISHED: usize = 3;
+/// Words of one encoded pose.
+const RECORD_WORDS: usize = 23;
+const SLOT_WORDS: usize = RECORD_WORDS + 1;
+
+/// A shared mapping, accessed only through 64-bit atomics. Atomic loads are
+/// sound on the read-only mappings of subscribers as they are lock-free here.
+struct Mapping {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping stays valid until dropped and all access is atomic.
+unsafe impl Send for Mapping {}
+
+impl Mapping {
+    /// Map the segment behind `fd`, closing `fd`.
+    fn new(fd: libc::c_int, len: usize, writable: bool) -> io::Result<Mapping> {
+        let prot = if writable {
+            libc::PROT_READ | libc::PROT_WRITE
+        } else {
+            libc::PROT_READ
+        };
+        let ptr = unsafe { libc::mmap(std::ptr::null_mut(), len, prot, libc::MAP_SHARED, fd, 0) };
+        let result = if ptr == libc::MAP_FAILED {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(Mapping { ptr, len })
+        };
+        unsafe { libc::close(fd) };
+        result
+    }
+
+    fn words(&self) -> &[AtomicU64] {
+        unsafe { std::slice::from_raw_parts(self.ptr as *const AtomicU64, self.len / 8) }
+    }
+
+    fn slot(&self, capacity: u64, k: u64) -> &[AtomicU64] {
+        let start = HEADER_WORDS + (k % capacity) as usize * SLOT_WORDS;
+        &self.words()[start..start + SLOT_WORDS]
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr, self.len) };
+    }
+}
+
+/// POSIX shared-memory names start with a slash; add it if missing.
+fn shm_name(name: &str) -> Result<CString> {
+    let name = if name.starts_with('/') {
+        name.to_string()
+    } else {
+        format!("/{}", name)
+    };
+    CString::new(name).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput).into())
+}
+
+/// Writes poses into a named segment; the segment is removed on drop.
+pub struct ShmPublisher {
+    map: Mapping,
+    name: CString,
+    capacity: u64,
+    published: u64,
+}
+
+impl ShmPublisher {
+    /// Create segment `name` holding the last `capacity` poses, replacing any
+    /// segment of that name. Subscribers of a replaced segment must re-open.
+    pub fn create(name: &str, capacity: usize) -> Result<Self> {
+        let name = shm_name(name)?;
+        let capacity = capacity.max(1);
+        let len = (HEADER_WORDS + capacity * SLOT_WORDS) * 8;
+        // macOS can't resize an existing segment, so start from a fresh one.
+        unsafe { libc::shm_unlink(name.as_ptr()) };
+        let fd = unsafe {
+            libc::shm_open(
+                name.as_ptr(),
+                libc::O_CREAT | libc::O_EXCL | libc::O_RDWR,
+                0o644 as libc::c_uint,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        if unsafe { libc::ftruncate(fd, len as libc::off_t) } != 0 {
+            let e = io::Error::last_os_error();
+            unsafe {
+                libc::close(fd);
+                libc::shm_unlink(name.as_ptr());
+            }
+            return Err(e.into());
+        }
+        let map = Mapping::new(fd, len, true).inspect_err(|_| unsafe {
+            libc::shm_unlink(name.as_ptr());
+        })?;
+        let words = map.words();
+        words[1].store(VERSION, Ordering::Relaxed);
+        words[2].store(capacity as u64, Ordering::Relaxed);
+        words[0].store(MAGIC, Ordering::Release);
+        Ok(Self {
+            map,
+            name,
+            capacity: capacity as u64,
+            published: 0,
+        })
+    }
+
+    /// Append `pose`, overwriting the oldest once the ring is full.
+    pub fn publish(&mut self, pose: &Pose) {
+        let k = self.published;
+        let slot = self.map.slot(self.capacity, k);
+        slot[0].store(2 * k + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        for (word, value) in slot[1..].iter().zip(encode(pose)) {
+            word.store(value, Ordering::Relaxed);
+        }
+        slot[0].store(2 * k + 2, Ordering::Release);
+        self.published = k + 1;
+        self.map.words()[PUBLISHED].store(self.published, Ordering::Release);
+    }
+}
+
+impl Drop for ShmPublisher {
+    fn drop(&mut self) {
+        unsafe { libc::shm_unlink(self.name.as_ptr()) };
+    }
+}
+
+/// Reads poses from a segment written by a [`ShmPublisher`], without blocking it.
+pub struct ShmSubscriber {
+    map: Mapping,
+    capacity: u64,
+    next: u64,
+    missed: u64,
+}
+
+impl ShmSubscriber {
+    /// Map segment `name` read-only. [`try_recv`](Self::try_recv) starts with the
+    /// next pose published after this call.
+    pub fn open(name: &str) -> Result<Self> {
+        let name = shm_name(name)?;
+        let fd = unsafe { libc::shm_open(name.as_ptr(), libc::O_RDONLY, 0 as libc::c_uint) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+            let e = io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(e.into());
+        }
+        let len = stat.st_size as usize;
+        if len < HEADER_WORDS * 8 {
+            unsafe { libc::close(fd) };
+            return Err(invalid_segment());
+        }
+        let map = Mapping::new(fd, len, false)?;
+        let words = map.words();
+        let capacity = words[2].load(Ordering::Relaxed);
+        if words[0].load(Ordering::Acquire) != MAGIC
+            || words[1].load(Ordering::Relaxed) != VERSION
+            || capacity == 0
+            || len < (HEADER_WORDS + capacity as usize * SLOT_WORDS) * 8
+        {
+            return Err(invalid_segment());
+        }
+        let next = words[PUBLISHED].load(Ordering::Acquire);
+        Ok(Self {
+            map,
+            capacity,
+            next,
+            missed: 0,
+        })
+    }
+
+    /// The next pose in order, or `None` if there is no new one yet.
+    pub fn try_recv(&mut self) -> Option<Pose> {
+        loop {
+            let published = self.map.words()[PUBLISHED].load(Ordering::Acquire);
+            if self.next >= published {
+                return None;
+            }
+            let oldest = published.saturating_sub(self.capacity);
+            if self.next < oldest {
+                self.missed += oldest - self.next;
+                self.next = oldest;
+            }
+            let k = self.next;
+            self.next += 1;
+            match self.read(k) {
+                Some(pose) => return Some(pose),
+                // Overwritten while reading.
+                None => self.missed += 1,
+            }
+        }
+    }
+
+    /// The most recently published pose, regardless of what was received.
+    pub fn latest(&self) -> Option<Pose> {
+        let published = self.map.words()[PUBLISHED].load(Ordering::Acquire);
+        self.read(published.checked_sub(1)?)
+    }
+
+    /// Poses overwritten before this subscriber read them.
+    pub fn missed(&self) -> u64 {
+        self.missed
+    }
+
+    fn read(&self, k: u64) -> Option<Pose> {
+        let slot = self.map.slot(self.capacity, k);
+        let sequence = slot[0].load(Ordering::Acquire);
+        if sequence != 2 * k + 2 {
+            return None;
+        }
+        let mut record = [0u64; RECORD_WORDS];
+        for (value, word) in record.iter_mut().zip(&slot[1..]) {
+            *value = word.load(Ordering::Relaxed);
+        }
+        fence(Ordering::Acquire);
+        (slot[0].load(Ordering::Relaxed) == sequence).then(|| decode(&record))
+    }
+}
+
+fn invalid_segment() -> crate::XvisioError {
+    io::Error::new(io::ErrorKind::InvalidData, "not an xvisio pose segment").into()
+}
+
+fn encode(pose: &Pose) -> [u64; RECORD_WORDS] {
+    let mut record = [0u64; RECORD_WORDS];
+    let floats = pose
+        .translation
+        .iter()
+        .chain(pose.rotation.iter().flatten())
+        .chain(&pose.quaternion)
+        .chain([&pose.host_timestamp_s, &pose.confidence])
+        .chain(&pose.euler_deg);
+    for (word, value) in record.iter_mut().zip(floats) {
+        *word = value.to_bits();
+    }
+    record[21] = pose.timestamp_us;
+    record[22] = pose.confidence_raw as u16 as u64 | (pose.rotation_source as u64) << 16;
+    record
+}
+
+fn decode(record: &[u64; RECORD_WORDS]) -> Pose {
+    let f = |i: usize| f64::from_bits(record[i]);
+    Pose {
+        translation: [f(0), f(1), f(2)],
+        rotation: [[f(3), f(4), f(5)], [f(6), f(7), f(8)], [f(9), f(10), f(11)]],
+        quaternion: [f(12), f(13), f(14), f(15)],
+        host_timestamp_s: f(16),
+        confidence: f(17),
+        euler_deg: [f(18), f(19), f(20)],
+        timestamp_us: record[21],
+        confidence_raw: record[22] as u16 as i16,
+        rotation_source: if record[22] >> 16 == RotationSource::Quaternion as u64 {
+            RotationSource::Quaternion
+        } else {
+            RotationSource::Matrix
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscriber_follows_and_skips_ahead() {
+        let name = format!("xvisio-test-{}", std::process::id());
+        let mut publisher = ShmPublisher::create(&name, 4).unwrap();
+        let pose = |us: u64| Pose {
+            timestamp_us: us,
+            confidence_raw: -2,
+            ..Pose::new([us as f64, 0.5, -1.0], [0.0, 0.0, 0.0, 1.0])
+        };
+        publisher.publish(&pose(1));
+        let mut subscriber = ShmSubscriber::open(&name).unwrap();
+        assert!(subscriber.try_recv().is_none());
+
+        publisher.publish(&pose(2));
+        let got = subscriber.try_recv().unwrap();
+        assert_eq!((got.timestamp_us, got.translation), (2, [2.0, 0.5, -1.0]));
+        assert_eq!(got.confidence_raw, -2);
+        assert_eq!(got.rotation_source, RotationSource::Quaternion);
+
+        for us in 3..=10 {
+            publisher.publish(&pose(us));
+        }
+        let received: Vec<u64> = std::iter::from_fn(|| subscriber.try_recv())
+            .map(|p| p.timestamp_us)
+            .collect();
+        assert_eq!(received, [7, 8, 9, 10]);
+        assert_eq!(subscriber.missed(), 4);
+        assert_eq!(subscriber.latest().unwrap().timestamp_us, 10);
+
+        drop(publisher);
+        assert!(ShmSubscriber::open(&name).is_err());
+    }
+}
@@ -1,123 +1,1853 @@
+use crate::capture::{CaptureWriter, RawRecord};
+use crate::imu::{GyroBiasEstimator, ImuCalibration};
 use crate::protocol;
-use crate::types::SlamSample;
+use crate::sched::{self, Priority};
+use crate::types::{Pose, SlamMode, SlamSample};
 use crate::{Result, XvisioError};
 use crossbeam_channel::{Receiver, Sender};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Step of the `start_slam` command sequence, reported through [`SlamConfig::on_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupPhase {
+    /// Sending the configure command (mode, UVC mode, embedded algo).
+    Configuring,
+    /// Re-opening the device handle after a USB re-enumeration.
+    Reopening,
+    /// Sending the stereo camera init/start commands.
+    StereoInit,
+    /// Sending the edge stream start command.
+    EdgeStreamStart,
+    /// A command is being retried after the device dropped off the bus.
+    Retrying { attempt: usize, max_attempts: usize },
+    /// Commands done; the reader thread is about to spawn.
+    Streaming,
+}
+
+/// One command of a [`StartupSequence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartupStep {
+    pub command: protocol::CommandBuilder,
+    /// Reported through [`SlamConfig::on_progress`] before the command is sent.
+    pub phase: StartupPhase,
+    /// Pause after the command, e.g. while the device applies a configure.
+    pub delay_after: Duration,
+    /// Re-open the command handle after the pause, for a device that
+    /// re-enumerates in response to the command.
+    pub reopen_after: bool,
+    /// Abort the start if the command fails; otherwise the failure is logged.
+    pub required: bool,
+}
+
+impl StartupStep {
+    /// A required step without pause or re-open.
+    pub fn new(command: protocol::CommandBuilder, phase: StartupPhase) -> Self {
+        Self {
+            command,
+            phase,
+            delay_after: Duration::ZERO,
+            reopen_after: false,
+            required: true,
+        }
+    }
+}
+
+/// Commands `start_slam` sends before the reader starts, see
+/// [`SlamConfig::startup_sequence`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StartupSequence {
+    pub steps: Vec<StartupStep>,
+}
+
+impl StartupSequence {
+    /// Configure, a 1 s pause, then edge stream start: the Windows/Linux sequence.
+    pub fn standard(
+        edge: bool,
+        uvc_mode: u8,
+        embedded_algo: bool,
+        rotation_enabled: bool,
+        flipped: bool,
+    ) -> Self {
+        let configure = protocol::CommandBuilder::configure(edge, uvc_mode, embedded_algo);
+        let edge_start =
+            protocol::CommandBuilder::edge_stream(edge as u8, rotation_enabled, flipped);
+        Self {
+            steps: vec![
+                StartupStep {
+                    delay_after: Duration::from_secs(1),
+                    ..StartupStep::new(configure, StartupPhase::Configuring)
+                },
+                StartupStep::new(edge_start, StartupPhase::EdgeStreamStart),
+            ],
+        }
+    }
+
+    /// Insert stereo camera init (50 ms pause) and start (300 ms pause) before the
+    /// last step, the edge stream start of [`standard`](Self::standard). Some
+    /// macOS setups need them for non-identity poses.
+    pub fn with_stereo_init(mut self, required: bool) -> Self {
+        let step = |command, delay_ms| StartupStep {
+            delay_after: Duration::from_millis(delay_ms),
+            required,
+            ..StartupStep::new(command, StartupPhase::StereoInit)
+        };
+        let at = self.steps.len().saturating_sub(1);
+        self.steps.splice(
+            at..at,
+            [
+                step(protocol::CommandBuilder::stereo_camera_init(), 50),
+                step(protocol::CommandBuilder::stereo_camera_start(), 300),
+            ],
+        );
+        self
+    }
+
+    /// The last edge stream command, which the rusb reader re-sends on a stall;
+    /// a plain edge stream start if the sequence has none.
+    #[cfg(feature = "usb")]
+    pub(crate) fn edge_stream_command(&self) -> [u8; protocol::REPORT_SIZE] {
+        self.steps
+            .iter()
+            .rev()
+            .find(|step| step.command.echo() == protocol::CMD_EDGE_STREAM)
+            .map_or_else(
+                || protocol::build_edge_stream_cmd(true),
+                |step| step.command.build(),
+            )
+    }
+}
+
+/// Change in a stream's condition, see [`SlamStream::events`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamEvent {
+    /// The device dropped off the bus; the stream has stopped.
+    Disconnected,
+    /// A [`PersistentStream`](crate::PersistentStream) re-established the stream.
+    Reconnected,
+    /// The device timestamp stopped advancing, see [`SlamStream::is_stalled`].
+    Stalled,
+    /// The device timestamp advances again after [`Stalled`](Self::Stalled).
+    Resumed,
+    /// Only identity poses since the start, see [`TrackingStatus::CamerasInactive`].
+    CamerasInactive,
+}
+
+/// Motion limits of [`SlamConfig::reject_outliers`]: a pose implying more, relative
+/// to the last accepted one, is discarded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutlierLimits {
+    /// Linear speed in m/s.
+    pub max_speed: f64,
+    /// Angular speed in rad/s.
+    pub max_angular_speed: f64,
+}
+
+impl Default for OutlierLimits {
+    /// 10 m/s and 30 rad/s (~1700°/s), well above head or hand motion.
+    fn default() -> Self {
+        Self {
+            max_speed: 10.0,
+            max_angular_speed: 30.0,
+        }
+    }
+}
+
+/// Where the time of `start_slam` went, see [`SlamStream::startup_timings`].
+/// Offsets count from the `start_slam` call.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StartupTimings {
+    /// Each [`StartupPhase`] as it was reported, so a retried step appears once
+    /// per attempt.
+    pub phases: Vec<(StartupPhase, Duration)>,
+    /// When `start_slam` returned the stream.
+    pub returned: Duration,
+    /// When the first tracking (non-identity) sample arrived; `None` until then.
+    pub first_sample: Option<Duration>,
+}
+
+/// Whether the device has started tracking, see [`SlamStream::tracking_status`].
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackingStatus {
+    /// Only identity poses so far.
+    Pending = 0,
+    /// A non-identity pose has been seen.
+    Tracking = 1,
+    /// Identity poses for [`CAMERAS_INACTIVE_SAMPLES`] in a row since the start: the
+    /// stereo cameras most likely never activated (uvcvideo bound on Linux, or
+    /// stereo init needed on macOS).
+    CamerasInactive = 2,
+}
+
+impl TrackingStatus {
+    fn from_u8(v: u8) -> Self {
+        match v {
+            1 => TrackingStatus::Tracking,
+            2 => TrackingStatus::CamerasInactive,
+            _ => TrackingStatus::Pending,
+        }
+    }
+}
+
+/// Identity poses (~1 s at 950 Hz) after which the cameras are reported inactive.
+pub const CAMERAS_INACTIVE_SAMPLES: u32 = 1000;
+
+/// Nominal device sample period, used to estimate packets lost in a gap.
+const SAMPLE_PERIOD_US: u32 = (1e6 / protocol::NOMINAL_RATE_HZ + 0.5) as u32;
+
+/// Events buffered for [`SlamStream::events`] before new ones are dropped.
+const EVENT_CAPACITY: usize = 32;
+
+/// How often a reader paused by [`SlamConfig::pause_when_idle`] checks for a reader.
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Loss counters of a [`SlamStream`], see [`SlamStream::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SlamStats {
+    /// Edge SLAM packets parsed by the reader, including [`outliers`](Self::outliers).
+    /// Host-processed reports count under [`unparsed_packets`](Self::unparsed_packets).
+    pub samples: u64,
+    /// Jumps in the device clock longer than one sample period: packets lost
+    /// between device and host.
+    pub gaps: u64,
+    /// Packets estimated lost in those gaps.
+    pub missed_samples: u64,
+    /// Samples dropped because the channel was full: the consumer fell behind.
+    pub channel_drops: u64,
+    /// Packets with a valid SLAM header but too short to parse: runts from the
+    /// device or a truncated transfer. Forwarded unparsed.
+    pub short_packets: u64,
+    /// Reports without a SLAM header, e.g. [`SlamMode::Mixed`] output whose format
+    /// is still unknown. Passed to the [`SlamConfig::host_processor`] if set, else
+    /// forwarded unparsed, see [`SlamStream::try_recv_unparsed`].
+    pub unparsed_packets: u64,
+    /// Samples discarded as physically implausible, see [`SlamConfig::reject_outliers`].
+    pub outliers: u64,
+    /// Samples waiting in the channel when read: the consumer's backlog, about
+    /// 1 ms of latency each at the full rate. See also [`SlamStream::host_now`].
+    pub queued: u64,
+}
+
+/// Reader-side counters behind [`SlamStats`].
+#[derive(Default)]
+struct StatsCounters {
+    samples: AtomicU64,
+    gaps: AtomicU64,
+    missed_samples: AtomicU64,
+    channel_drops: AtomicU64,
+    short_packets: AtomicU64,
+    unparsed_packets: AtomicU64,
+    outliers: AtomicU64,
+}
+
+/// SLAM startup backend on macOS; ignored elsewhere, where hidapi is always used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacBackend {
+    /// Detach the kernel driver and drive the device through libusb.
+    /// Triggers a USB re-enumeration, which can leave the cameras inactive.
+    Rusb,
+    /// Open the HID interface shared through IOKit (hidapi) and never detach;
+    /// commands and the interrupt pipe both go through hidapi.
+    IokitNoDetach,
+}
+
+impl MacBackend {
+    /// Parse an `XVISIO_MAC_BACKEND` value: `rusb`, or `hidapi` / `iokit`.
+    pub fn parse(s: &str) -> Option<MacBackend> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "rusb" => Some(MacBackend::Rusb),
+            "hidapi" | "iokit" => Some(MacBackend::IokitNoDetach),
+            _ => None,
+        }
+    }
+}
+
+/// SLAM backend off macOS, see [`SlamConfig::backend`]; macOS uses [`MacBackend`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Commands and the interrupt pipe through hidapi (hidraw on Linux).
+    #[default]
+    Hidapi,
+    /// Detach the kernel HID driver and drive the device through libusb, one
+    /// handle for commands and the reader. Needs the `usb` feature.
+    Rusb,
+}
+
+/// Clock the reader stamps samples with, see [`SlamConfig::host_clock`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HostClock {
+    /// Steady time since the stream started (`Instant`); immune to clock changes.
+    #[default]
+    Monotonic,
+    /// Wall-clock time since the Unix epoch (`SystemTime`), comparable across
+    /// processes and with other timestamped feeds, but it jumps when the system
+    /// clock is adjusted.
+    SystemRealtime,
+}
+
+impl HostClock {
+    /// Current time on this clock, for a stream started at `epoch`.
+    fn now(self, epoch: Instant) -> Duration {
+        match self {
+            HostClock::Monotonic => epoch.elapsed(),
+            HostClock::SystemRealtime => SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Host-side pose computation for reports the device doesn't send as edge
+/// SLAM packets, e.g. the [`SlamMode::Mixed`] output, see
+/// [`SlamConfig::host_processor`].
+///
+/// Runs on the reader thread for every such report, so it must keep up with the
+/// ~950 Hz interrupt rate or samples are lost in the transport.
+pub trait HostSlamProcessor: Send {
+    /// Compute a pose from one raw interrupt report, or `None` to forward it
+    /// unparsed. `timestamp_us` should be device time; the stall watchdog and
+    /// `sequence` run on it.
+    fn process(&mut self, data: &[u8]) -> Option<Pose>;
+}
+
+impl<F: FnMut(&[u8]) -> Option<Pose> + Send> HostSlamProcessor for F {
+    fn process(&mut self, data: &[u8]) -> Option<Pose> {
+        self(data)
+    }
+}
+
+/// Options for [`Device::start_slam_with_config`](crate::Device::start_slam_with_config).
+pub struct SlamConfig {
+    pub mode: SlamMode,
+    /// Called on the thread running `start_slam` as the startup sequence progresses.
+    pub on_progress: Option<Box<dyn Fn(StartupPhase) + Send + Sync>>,
+    /// File receiving every raw interrupt payload, see [`crate::capture`].
+    pub raw_capture: Option<PathBuf>,
+    /// Shared-memory segment name and ring capacity poses are published to,
+    /// see [`crate::shm`].
+    #[cfg(all(feature = "shm", unix))]
+    pub shm: Option<(String, usize)>,
+    /// Forward only every nth parsed sample to the channel (1 = all, ~950 Hz).
+    pub output_divisor: u32,
+    /// How long the device timestamp may stay unchanged before the stream counts
+    /// as stalled, see [`SlamStream::is_stalled`]. `None` disables the watchdog.
+    pub stall_timeout: Option<Duration>,
+    /// Stop the stream once a stall is detected.
+    pub stop_on_stall: bool,
+    /// Samples buffered between the reader thread and the consumer.
+    pub channel_capacity: usize,
+    /// Make `start_slam` wait this long for the first tracking sample.
+    pub first_sample_timeout: Option<Duration>,
+    /// Fixed device-to-rig extrinsic composed onto every sample, see [`Pose::compose`].
+    pub mount_transform: Option<Pose>,
+    /// Set to abort `start_slam` between retries, see [`SlamConfig::cancel_token`].
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// macOS backend; `None` reads `XVISIO_MAC_BACKEND`, defaulting to [`MacBackend::Rusb`].
+    pub mac_backend: Option<MacBackend>,
+    /// Backend on every other platform, see [`SlamConfig::backend`].
+    pub backend: Backend,
+    /// Recent poses kept for [`SlamStream::pose_at`]; 0 disables the history.
+    pub history_depth: usize,
+    /// Reader-thread read timeout; `None` uses the backend default
+    /// (100 ms hidapi, 200 ms rusb).
+    pub read_timeout: Option<Duration>,
+    /// `flipped` flag of the edge stream command, see [`SlamConfig::flipped`].
+    pub flipped: bool,
+    /// On a full channel drop the oldest sample instead of the newest,
+    /// see [`SlamConfig::keep_alive`].
+    pub keep_alive: bool,
+    /// Stamp samples with [`SlamSample::host_elapsed`].
+    pub host_elapsed: bool,
+    /// Clock behind the host timestamps, see [`SlamConfig::host_clock`].
+    pub host_clock: HostClock,
+    /// How long the channels may stay full before the reader pauses,
+    /// see [`SlamConfig::pause_when_idle`].
+    pub pause_when_idle: Option<Duration>,
+    /// Consecutive read timeouts after which the rusb reader re-sends the edge
+    /// stream start, see [`SlamConfig::restream_on_stall`].
+    pub restream_on_stall: Option<u32>,
+    /// Estimator whose bias the reader subtracts from every gyro reading,
+    /// see [`SlamConfig::remove_gyro_bias`].
+    pub gyro_bias: Option<GyroBiasEstimator>,
+    /// Correction applied to every IMU reading, see [`SlamConfig::imu_calibration`].
+    pub imu_calibration: Option<ImuCalibration>,
+    /// Scheduling priority of the reader thread, see [`SlamConfig::thread_priority`].
+    pub thread_priority: Priority,
+    /// CPU core the reader thread is pinned to, see [`SlamConfig::thread_affinity`].
+    pub thread_affinity: Option<usize>,
+    /// Computes poses from non-edge reports, see [`SlamConfig::host_processor`].
+    pub host_processor: Option<Arc<Mutex<dyn HostSlamProcessor>>>,
+    /// Limits beyond which samples are discarded, see [`SlamConfig::reject_outliers`].
+    pub reject_outliers: Option<OutlierLimits>,
+    /// Samples after which the stream stops itself, see [`SlamConfig::max_samples`].
+    pub max_samples: Option<u64>,
+    /// Run time after which the stream stops itself, see [`SlamConfig::max_duration`].
+    pub max_duration: Option<Duration>,
+    /// Header the reader recognizes SLAM reports by; `None` derives it from the
+    /// edge stream start command, see [`SlamConfig::stream_header`].
+    pub stream_header: Option<[u8; 3]>,
+    /// Commands sent instead of the backend's own sequence,
+    /// see [`SlamConfig::startup_sequence`].
+    pub startup_sequence: Option<StartupSequence>,
+}
+
+impl SlamConfig {
+    pub fn new(mode: SlamMode) -> Self {
+        Self {
+            mode,
+            on_progress: None,
+            raw_capture: None,
+            #[cfg(all(feature = "shm", unix))]
+            shm: None,
+            output_divisor: 1,
+            stall_timeout: Some(Duration::from_secs(1)),
+            stop_on_stall: false,
+            channel_capacity: 256,
+            first_sample_timeout: None,
+            mount_transform: None,
+            cancel: None,
+            mac_backend: None,
+            backend: Backend::Hidapi,
+            history_depth: 0,
+            read_timeout: None,
+            flipped: false,
+            keep_alive: false,
+            host_elapsed: false,
+            host_clock: HostClock::Monotonic,
+            pause_when_idle: None,
+            restream_on_stall: None,
+            gyro_bias: None,
+            imu_calibration: None,
+            thread_priority: Priority::Normal,
+            thread_affinity: None,
+            host_processor: None,
+            stream_header: None,
+            startup_sequence: None,
+            reject_outliers: None,
+            max_samples: None,
+            max_duration: None,
+        }
+    }
+
+    /// Discard samples whose pose moved faster than `limits` allow since the last
+    /// accepted one, e.g. a corrupt packet teleporting the position for one frame.
+    /// Discarded samples are counted in [`SlamStats::outliers`].
+    ///
+    /// After 10 rejections in a row the filter accepts the next pose as the new
+    /// reference, so a genuine jump (e.g. relocalization) only costs ~10 ms.
+    pub fn reject_outliers(mut self, limits: OutlierLimits) -> Self {
+        self.reject_outliers = Some(limits);
+        self
+    }
+
+    /// Stop the stream once `n` samples have been sent to the channel (after
+    /// [`output_divisor`](Self::output_divisor)), e.g. for bounded captures.
+    /// Samples dropped on a full channel don't count; 0 counts as 1.
+    /// `recv` then returns the queued samples, followed by
+    /// [`XvisioError::StreamStopped`].
+    pub fn max_samples(mut self, n: u64) -> Self {
+        self.max_samples = Some(n.max(1));
+        self
+    }
+
+    /// Stop the stream once it has run for `duration`, counted from the reader
+    /// thread's start; like [`max_samples`](Self::max_samples) otherwise.
+    pub fn max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+        self
+    }
+
+    /// Recognize SLAM reports by `header` instead of
+    /// [`SLAM_HEADER`](protocol::SLAM_HEADER), the echo of the edge stream start
+    /// command, for firmware variants that echo differently. Reports with this
+    /// header are parsed with the standard layout; raw captures keep the original
+    /// bytes, so [`crate::replay_raw`] won't recognize them.
+    pub fn stream_header(mut self, header: [u8; 3]) -> Self {
+        self.stream_header = Some(header);
+        self
+    }
+
+    /// Send `sequence` instead of the backend's built-in one, e.g. extra pauses
+    /// or re-opens for troublesome firmware. Start from
+    /// [`StartupSequence::standard`]; the commands built from `mode` and `flipped`
+    /// are then not sent. Disconnect retries on macOS hidapi and the rusb preconditioning
+    /// cycles still apply.
+    pub fn startup_sequence(mut self, sequence: StartupSequence) -> Self {
+        self.startup_sequence = Some(sequence);
+        self
+    }
+
+    /// Turn reports that don't parse as edge SLAM packets into samples with
+    /// `processor`, e.g. a host VIO backend consuming the [`SlamMode::Mixed`]
+    /// output. Its poses go through the same pipeline as edge poses (mount
+    /// transform, history, decimation) with `imu: None` and zeroed `raw_extended`;
+    /// reports it returns `None` for are still forwarded unparsed.
+    ///
+    /// The processor is shared by every stream started from this config, so its
+    /// state survives a [`PersistentStream`](crate::PersistentStream) reconnect.
+    pub fn host_processor(mut self, processor: impl HostSlamProcessor + 'static) -> Self {
+        self.host_processor = Some(Arc::new(Mutex::new(processor)));
+        self
+    }
+
+    /// Raise the reader thread's scheduling priority, so CPU contention doesn't
+    /// delay the interrupt reads and cost samples.
+    ///
+    /// Applied by the thread itself when it starts; if the OS refuses (e.g. no
+    /// realtime privileges) a warning is logged and the stream runs at default
+    /// priority.
+    pub fn thread_priority(mut self, priority: Priority) -> Self {
+        self.thread_priority = priority;
+        self
+    }
+
+    /// Pin the reader thread to CPU core `core` (0-based). Supported on Linux,
+    /// Android and Windows; elsewhere, or for a core that doesn't exist, a
+    /// warning is logged and the thread stays unpinned.
+    pub fn thread_affinity(mut self, core: usize) -> Self {
+        self.thread_affinity = Some(core);
+        self
+    }
+
+    /// Correct every IMU reading with `calibration` in the reader thread, e.g. one
+    /// from [`Device::calibrate_imu_at_rest`](crate::Device::calibrate_imu_at_rest).
+    /// Applied before [`remove_gyro_bias`](Self::remove_gyro_bias).
+    pub fn imu_calibration(mut self, calibration: ImuCalibration) -> Self {
+        self.imu_calibration = Some(calibration);
+        self
+    }
+
+    /// Estimate the gyroscope bias while the device rests and subtract it from
+    /// `imu.gyroscope` in the reader thread. Readings pass through unchanged
+    /// until `estimator` has seen its first still window, so keep the device
+    /// still for a moment after starting.
+    pub fn remove_gyro_bias(mut self, estimator: GyroBiasEstimator) -> Self {
+        self.gyro_bias = Some(estimator);
+        self
+    }
+
+    /// Re-send the edge stream start on the reader's handle after `timeouts`
+    /// consecutive read timeouts, instead of waiting on a silent pipe forever.
+    ///
+    /// Recovers a device that stopped streaming on its own without reopening it.
+    /// Only the macOS rusb backend keeps a handle it can send commands on; the
+    /// hidapi backends ignore this. Each timeout lasts
+    /// [`read_timeout`](Self::read_timeout), 200 ms by default.
+    pub fn restream_on_stall(mut self, timeouts: u32) -> Self {
+        self.restream_on_stall = Some(timeouts.max(1));
+        self
+    }
+
+    /// Also stamp each sample with the exact host time as a `Duration`, see
+    /// [`SlamSample::host_elapsed`].
+    ///
+    /// `host_timestamp_s` is the same reading as f64 seconds, which is plenty for
+    /// short sessions; its resolution coarsens as the session grows, so long
+    /// captures feeding sensor fusion should difference `host_elapsed` instead.
+    pub fn host_elapsed(mut self, enabled: bool) -> Self {
+        self.host_elapsed = enabled;
+        self
+    }
+
+    /// Stop reading the device once the sample channel and every subscriber have
+    /// stayed full for `after`, i.e. nobody is consuming, and resume as soon as
+    /// one of them has room again.
+    ///
+    /// The paused reader costs no CPU, and on macOS (rusb) the endpoint is no
+    /// longer polled; with hidapi the OS keeps polling it. The device keeps
+    /// streaming either way, so the pause shows up as a gap in [`SlamStats`] and
+    /// the consumer resumes with the samples queued before it.
+    /// [`flush`](SlamStream::flush) skips those. Ignored with a
+    /// [`channel_capacity`](Self::channel_capacity) of 0 and with
+    /// [`keep_alive`](Self::keep_alive), whose channel never stays full.
+    pub fn pause_when_idle(mut self, after: Duration) -> Self {
+        self.pause_when_idle = Some(after);
+        self
+    }
+
+    /// Choose the clock behind `pose.host_timestamp_s` and
+    /// [`SlamSample::host_elapsed`].
+    ///
+    /// With [`HostClock::SystemRealtime`] both count from the Unix epoch instead of
+    /// the stream start. As f64 seconds that is ~0.5 µs resolution, so enable
+    /// [`host_elapsed`](Self::host_elapsed) for exact nanoseconds.
+    pub fn host_clock(mut self, clock: HostClock) -> Self {
+        self.host_clock = clock;
+        self
+    }
+
+    /// Keep the channel fresh while the consumer pauses: when it is full the reader
+    /// discards the oldest queued sample to make room, so a consumer that resumes
+    /// after a stall reads recent poses instead of ones from before it. Each
+    /// eviction counts as a [`SlamStats::channel_drops`]. Overrides
+    /// [`pause_when_idle`](Self::pause_when_idle).
+    ///
+    /// No idle auto-stop is known on the XR50, and the reader services the
+    /// interrupt pipe at the full rate whether or not anyone consumes, so the
+    /// device side needs no keep-alive. There is no harmless status command to
+    /// ping it with either (see [`Device::read_state`](crate::Device::read_state)).
+    pub fn keep_alive(mut self, enabled: bool) -> Self {
+        self.keep_alive = enabled;
+        self
+    }
+
+    /// Ask the firmware for its flipped coordinate system, meant for a device
+    /// mounted upside down.
+    ///
+    /// The flag is passed through as is; which axes the firmware mirrors hasn't
+    /// been measured yet (see PROTOCOL.md). For a known mounting, a
+    /// [`mount_transform`](Self::mount_transform) has a documented effect.
+    pub fn flipped(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+
+    /// Set how long each reader-thread read blocks.
+    ///
+    /// While packets flow, reads return every ~1 ms and `stop()` is immediate; on an
+    /// idle pipe the stop flag is only seen once a read times out, so this also
+    /// bounds how long `stop()` can take.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout.max(Duration::from_millis(1)));
+        self
+    }
+
+    /// Keep the last `depth` poses (at the full ~950 Hz rate) for
+    /// [`SlamStream::pose_at`], e.g. 1000 for about one second.
+    pub fn history_depth(mut self, depth: usize) -> Self {
+        self.history_depth = depth;
+        self
+    }
+
+    /// Choose the backend off macOS. [`Backend::Rusb`] is a fallback for Linux
+    /// setups where hidraw misbehaves; it needs write access to the USB device
+    /// node rather than the hidraw one, and the kernel driver is re-attached when
+    /// the stream ends. On Windows libusb can't claim the HID interface, so it
+    /// only works with the WinUSB driver installed.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Choose the macOS backend explicitly instead of via `XVISIO_MAC_BACKEND`.
+    pub fn mac_backend(mut self, backend: MacBackend) -> Self {
+        self.mac_backend = Some(backend);
+        self
+    }
+
+    /// Abort `start_slam` with [`XvisioError::Cancelled`] once `token` is set.
+    /// Checked before every retry, so a cancel takes effect within one retry delay.
+    pub fn cancel_token(mut self, token: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Report poses in rig coordinates: `mount` is the rig's pose in the device frame.
+    pub fn mount_transform(mut self, mount: Pose) -> Self {
+        self.mount_transform = Some(mount);
+        self
+    }
+
+    /// Block `start_slam` until the first tracking sample arrives, failing with
+    /// [`XvisioError::FirstSampleTimeout`] after `timeout`.
+    ///
+    /// Identity poses, which the device emits when its cameras are unavailable,
+    /// don't count. The wait doesn't consume any samples.
+    pub fn wait_for_first_sample(mut self, timeout: Duration) -> Self {
+        self.first_sample_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the sample channel capacity (default 256, ~270 ms at 950 Hz).
+    ///
+    /// Samples arriving while the channel is full are dropped, so a small capacity
+    /// trades loss for latency. 0 makes a rendezvous channel: a sample is only
+    /// delivered if the consumer is blocked in `recv` when it arrives.
+    pub fn channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = capacity;
+        self
+    }
+
+    /// Set the stall watchdog timeout, `None` to disable it.
+    ///
+    /// The watchdog uses the device clock rather than pose values, so a device
+    /// held still doesn't trip it.
+    pub fn stall_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.stall_timeout = timeout;
+        self
+    }
+
+    /// Stop the stream when the watchdog detects a stall.
+    pub fn stop_on_stall(mut self, stop: bool) -> Self {
+        self.stop_on_stall = stop;
+        self
+    }
+
+    /// Decimate the stream to every nth sample, e.g. 10 for ~95 Hz.
+    ///
+    /// The configure command has no rate field, so the device still streams at
+    /// ~950 Hz and the reader drops the rest before they reach the channel.
+    pub fn output_divisor(mut self, n: u32) -> Self {
+        self.output_divisor = n.max(1);
+        self
+    }
+
+    /// Record raw packets to `path` for replay with [`crate::replay_raw`].
+    pub fn raw_capture(mut self, path: impl Into<PathBuf>) -> Self {
+        self.raw_capture = Some(path.into());
+        self
+    }
+
+    /// Publish the forwarded poses to shared-memory segment `name`, keeping the
+    /// last `capacity` for [`crate::shm::ShmSubscriber`]s in other processes.
+    #[cfg(all(feature = "shm", unix))]
+    pub fn publish_shm(mut self, name: impl Into<String>, capacity: usize) -> Self {
+        self.shm = Some((name.into(), capacity));
+        self
+    }
+
+    /// Set the startup progress callback.
+    pub fn progress(mut self, f: impl Fn(StartupPhase) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Box::new(f));
+        self
+    }
+
+    fn open_capture(&self) -> Result<Option<CaptureWriter>> {
+        self.raw_capture
+            .as_deref()
+            .map(CaptureWriter::create)
+            .transpose()
+    }
+
+    #[cfg(all(feature = "shm", unix))]
+    fn open_shm(&self) -> Result<Option<crate::shm::ShmPublisher>> {
+        self.shm
+            .as_ref()
+            .map(|(name, capacity)| crate::shm::ShmPublisher::create(name, *capacity))
+            .transpose()
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<()> {
+        match &self.cancel {
+            Some(token) if token.load(Ordering::Relaxed) => Err(XvisioError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Phases reported by one `start_slam`, for [`StartupTimings`] and
+/// [`SlamConfig::on_progress`].
+pub(crate) struct StartupProgress<'a> {
+    pub(crate) config: &'a SlamConfig,
+    phases: RefCell<Vec<(StartupPhase, Instant)>>,
+}
+
+impl<'a> StartupProgress<'a> {
+    pub(crate) fn new(config: &'a SlamConfig) -> Self {
+        Self {
+            config,
+            phases: RefCell::default(),
+        }
+    }
+
+    pub(crate) fn report(&self, phase: StartupPhase) {
+        log::debug!("SLAM startup: {:?}", phase);
+        self.phases.borrow_mut().push((phase, Instant::now()));
+        if let Some(f) = &self.config.on_progress {
+            f(phase);
+        }
+    }
+
+    pub(crate) fn into_phases(self) -> Vec<(StartupPhase, Instant)> {
+        self.phases.into_inner()
+    }
+}
 
 /// Handle to an active SLAM data stream.
 ///
 /// Receives ~950 Hz pose data from a background reader thread that
 /// reads HID interrupt reports via hidapi (Windows/Linux) or rusb (macOS).
+///
+/// Reports that don't parse as edge SLAM packets (e.g. the undocumented
+/// Mixed-mode output) are forwarded unparsed, see [`SlamStream::try_recv_unparsed`].
+///
+/// Parsed samples don't allocate: [`SlamSample`] is plain inline data, the
+/// reader reuses one packet buffer, and the channel's
+/// [`channel_capacity`](SlamConfig::channel_capacity) slots are allocated once
+/// at start. Each sample costs one fixed-size copy into the channel and one out.
 pub struct SlamStream {
     receiver: Receiver<SlamSample>,
+    unparsed: Receiver<RawRecord>,
+    events: Receiver<StreamEvent>,
     stop_flag: Arc<AtomicBool>,
+    stalled: Arc<AtomicBool>,
+    tracking: Arc<AtomicU8>,
+    history: Option<Arc<Mutex<PoseHistory>>>,
+    stats: Arc<StatsCounters>,
+    subscribers: Arc<Mutex<Vec<Sender<SlamSample>>>>,
+    channel_capacity: usize,
+    /// Start of the reader's host clock, see [`HostClock`].
+    epoch: Instant,
+    host_clock: HostClock,
+    startup: StartupTimings,
+    startup_began: Instant,
+    /// Set by the reader on the first tracking sample.
+    first_tracking: Arc<OnceLock<Instant>>,
     thread: Option<std::thread::JoinHandle<()>>,
     /// Prevents hid_exit() on macOS while the reader thread is using the HidDevice.
     /// Only used when the hidapi backend is active (Windows/Linux).
     _api: Option<hidapi::HidApi>,
 }
 
-impl SlamStream {
-    /// Start the SLAM streaming thread using hidapi (Windows/Linux).
-    pub(crate) fn start_hidapi(
-        device: hidapi::HidDevice,
-        api: hidapi::HidApi,
-    ) -> Result<SlamStream> {
-        let (sender, receiver) = crossbeam_channel::bounded(256);
-        let stop_flag = Arc::new(AtomicBool::new(false));
-        let stop_clone = stop_flag.clone();
+impl SlamStream {
+    /// Start the SLAM streaming thread using hidapi (Windows/Linux).
+    pub(crate) fn start_hidapi(
+        device: hidapi::HidDevice,
+        api: hidapi::HidApi,
+        config: &SlamConfig,
+    ) -> Result<SlamStream> {
+        Self::spawn(config, Some(api), move |sink| {
+            slam_reader_hidapi(device, sink)
+        })
+    }
+
+    /// Start the SLAM streaming thread using rusb (macOS). `edge_start` is the
+    /// command re-sent with [`SlamConfig::restream_on_stall`].
+    #[cfg(feature = "usb")]
+    pub(crate) fn start_rusb(
+        handle: rusb::DeviceHandle<rusb::GlobalContext>,
+        config: &SlamConfig,
+        edge_start: [u8; protocol::REPORT_SIZE],
+    ) -> Result<SlamStream> {
+        let restream = config.restream_on_stall.map(|n| (n, edge_start));
+        Self::spawn(config, None, move |sink| {
+            slam_reader_rusb(handle, sink, restream)
+        })
+    }
+
+    fn spawn(
+        config: &SlamConfig,
+        api: Option<hidapi::HidApi>,
+        reader: impl FnOnce(ReaderSink) + Send + 'static,
+    ) -> Result<SlamStream> {
+        let (sender, receiver) = crossbeam_channel::bounded(config.channel_capacity);
+        let (unparsed_sender, unparsed) = crossbeam_channel::bounded(config.channel_capacity);
+        let (event_sender, events) = crossbeam_channel::bounded(EVENT_CAPACITY);
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let stalled = Arc::new(AtomicBool::new(false));
+        let tracking = Arc::new(AtomicU8::new(TrackingStatus::Pending as u8));
+        let (first_sample, first_sample_ready) = crossbeam_channel::bounded(1);
+        let history = (config.history_depth > 0)
+            .then(|| Arc::new(Mutex::new(PoseHistory::new(config.history_depth))));
+        let stats = Arc::new(StatsCounters::default());
+        let subscribers = Arc::new(Mutex::new(Vec::new()));
+        let first_tracking = Arc::new(OnceLock::new());
+        let epoch = Instant::now();
+        let sink = ReaderSink {
+            epoch,
+            samples: sender,
+            unparsed: unparsed_sender,
+            events: event_sender,
+            stop_flag: stop_flag.clone(),
+            capture: config.open_capture()?,
+            #[cfg(all(feature = "shm", unix))]
+            shm: config.open_shm()?,
+            output_divisor: config.output_divisor.max(1),
+            parsed: 0,
+            stall_timeout: config.stall_timeout,
+            stop_on_stall: config.stop_on_stall,
+            stalled: stalled.clone(),
+            last_timestamp_us: 0,
+            last_advance: Instant::now(),
+            first_sample: Some(first_sample),
+            first_tracking: first_tracking.clone(),
+            mount_transform: config.mount_transform,
+            tracking: tracking.clone(),
+            identity_samples: 0,
+            history: history.clone(),
+            read_timeout: config.read_timeout,
+            sequence: None,
+            stats: stats.clone(),
+            subscribers: subscribers.clone(),
+            evict: config.keep_alive.then(|| receiver.clone()),
+            host_elapsed: config.host_elapsed,
+            host_clock: config.host_clock,
+            pause_when_idle: config.pause_when_idle,
+            full_since: None,
+            gyro_bias: config.gyro_bias.clone(),
+            imu_calibration: config.imu_calibration,
+            host_processor: config.host_processor.clone(),
+            header: config.stream_header.unwrap_or(protocol::SLAM_HEADER),
+            sent: 0,
+            max_samples: config.max_samples,
+            max_duration: config.max_duration,
+            outliers: config.reject_outliers.map(OutlierFilter::new),
+        };
+
+        let (priority, core) = (config.thread_priority, config.thread_affinity);
+        let thread = std::thread::Builder::new()
+            .name("xvisio-slam".into())
+            .spawn(move || {
+                sched::configure_current_thread(priority, core);
+                reader(sink)
+            })
+            .map_err(|e| XvisioError::HidCommand(format!("Failed to spawn SLAM thread: {}", e)))?;
+
+        let stream = SlamStream {
+            receiver,
+            unparsed,
+            events,
+            stop_flag,
+            stalled,
+            tracking,
+            history,
+            stats,
+            subscribers,
+            channel_capacity: config.channel_capacity,
+            epoch,
+            host_clock: config.host_clock,
+            startup: StartupTimings::default(),
+            startup_began: Instant::now(),
+            first_tracking,
+            thread: Some(thread),
+            _api: api,
+        };
+        if let Some(timeout) = config.first_sample_timeout {
+            first_sample_ready
+                .recv_timeout(timeout)
+                .map_err(|e| match e {
+                    crossbeam_channel::RecvTimeoutError::Timeout => {
+                        XvisioError::FirstSampleTimeout(timeout)
+                    }
+                    crossbeam_channel::RecvTimeoutError::Disconnected => stream.stopped_error(),
+                })?;
+        }
+        Ok(stream)
+    }
+
+    /// Receive the next SLAM sample (blocks until available).
+    pub fn recv(&self) -> Result<SlamSample> {
+        self.receiver.recv().map_err(|_| self.stopped_error())
+    }
+
+    /// Try to receive a SLAM sample without blocking.
+    pub fn try_recv(&self) -> Option<SlamSample> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Discard every queued sample, e.g. the warm-up poses buffered while tracking
+    /// converged, so the next `recv` returns one from now on. Returns how many
+    /// were dropped. Subscribers (see [`subscribe`](Self::subscribe)) keep theirs.
+    pub fn flush(&self) -> usize {
+        self.receiver.try_iter().count()
+    }
+
+    /// Receive a SLAM sample with a timeout.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<SlamSample> {
+        self.receiver.recv_timeout(timeout).map_err(|e| match e {
+            crossbeam_channel::RecvTimeoutError::Timeout => XvisioError::Timeout,
+            crossbeam_channel::RecvTimeoutError::Disconnected => self.stopped_error(),
+        })
+    }
+
+    fn stopped_error(&self) -> XvisioError {
+        stopped_error(&self.stalled)
+    }
+
+    /// Blocking iterator over samples, ending once the reader thread stops.
+    pub fn iter(&self) -> SlamIter<'_> {
+        SlamIter(self.receiver.iter())
+    }
+
+    /// Iterator over the samples already queued, without blocking.
+    pub fn try_iter(&self) -> impl Iterator<Item = SlamSample> + '_ {
+        self.receiver.try_iter()
+    }
+
+    /// Blocking iterator delivering one sample per `hz` interval of device time,
+    /// interpolated to the interval boundary, see [`FixedRate`].
+    pub fn fixed_rate(&self, hz: f64) -> impl Iterator<Item = SlamSample> + '_ {
+        let mut rate = FixedRate::new(hz);
+        self.iter().filter_map(move |sample| rate.push(sample))
+    }
+
+    /// Add a consumer with its own queue of [`SlamConfig::channel_capacity`] samples,
+    /// fed alongside this stream from the same reader. A subscriber that falls
+    /// behind only drops its own samples (not counted in [`stats`](Self::stats));
+    /// dropping it stops the feed.
+    pub fn subscribe(&self) -> SlamReceiver {
+        let (sender, receiver) = crossbeam_channel::bounded(self.channel_capacity);
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+        SlamReceiver {
+            receiver,
+            stalled: self.stalled.clone(),
+        }
+    }
+
+    /// Channel of [`StreamEvent`]s, so e.g. a UI thread can follow disconnects and
+    /// stalls while another thread consumes samples. Clones share one queue, each
+    /// event reaching one of them; events beyond a small backlog are dropped. The
+    /// channel closes once the reader thread stops.
+    pub fn events(&self) -> Receiver<StreamEvent> {
+        self.events.clone()
+    }
+
+    /// Try to receive a report that didn't parse as an edge SLAM packet.
+    ///
+    /// In [`SlamMode::Mixed`] the device emits a format this crate can't decode yet;
+    /// those reports land here instead of being dropped. Unread reports beyond
+    /// [`SlamConfig::channel_capacity`] are discarded.
+    pub fn try_recv_unparsed(&self) -> Option<RawRecord> {
+        self.unparsed.try_recv().ok()
+    }
+
+    /// Keep the phases `start_slam` reported since `began`.
+    pub(crate) fn record_startup(&mut self, began: Instant, phases: Vec<(StartupPhase, Instant)>) {
+        self.startup = StartupTimings {
+            phases: phases
+                .into_iter()
+                .map(|(phase, at)| (phase, at.saturating_duration_since(began)))
+                .collect(),
+            returned: began.elapsed(),
+            first_sample: None,
+        };
+        self.startup_began = began;
+    }
+
+    /// How long each step of `start_slam` took and when the first tracking sample
+    /// arrived, e.g. to find which retries dominate a slow macOS start.
+    pub fn startup_timings(&self) -> StartupTimings {
+        StartupTimings {
+            first_sample: self
+                .first_tracking
+                .get()
+                .map(|at| at.saturating_duration_since(self.startup_began)),
+            ..self.startup.clone()
+        }
+    }
+
+    /// Check if the stream is still active.
+    pub fn is_active(&self) -> bool {
+        !self.stop_flag.load(Ordering::Relaxed)
+    }
+
+    /// Pose at device time `device_time_us`, interpolated between the two
+    /// bracketing samples of the history (see [`SlamConfig::history_depth`]).
+    ///
+    /// `None` if the history is disabled or the time lies outside it.
+    pub fn pose_at(&self, device_time_us: u64) -> Option<Pose> {
+        self.history.as_ref()?.lock().ok()?.at(device_time_us)
+    }
+
+    /// Whether the device is producing real poses, or only identity poses
+    /// because its cameras are inactive.
+    pub fn tracking_status(&self) -> TrackingStatus {
+        TrackingStatus::from_u8(self.tracking.load(Ordering::Relaxed))
+    }
+
+    /// True once a non-identity pose has been received.
+    pub fn tracking_initialized(&self) -> bool {
+        self.tracking_status() == TrackingStatus::Tracking
+    }
+
+    /// Check if reads succeed but the device timestamp has stopped advancing,
+    /// i.e. the device has wedged and samples are stale. Clears if it recovers.
+    pub fn is_stalled(&self) -> bool {
+        self.stalled.load(Ordering::Relaxed)
+    }
+
+    /// Packet loss so far, split into transport gaps (device to host) and
+    /// channel drops (consumer too slow).
+    pub fn stats(&self) -> SlamStats {
+        SlamStats {
+            samples: self.stats.samples.load(Ordering::Relaxed),
+            gaps: self.stats.gaps.load(Ordering::Relaxed),
+            missed_samples: self.stats.missed_samples.load(Ordering::Relaxed),
+            channel_drops: self.stats.channel_drops.load(Ordering::Relaxed),
+            short_packets: self.stats.short_packets.load(Ordering::Relaxed),
+            unparsed_packets: self.stats.unparsed_packets.load(Ordering::Relaxed),
+            outliers: self.stats.outliers.load(Ordering::Relaxed),
+            queued: self.receiver.len() as u64,
+        }
+    }
+
+    /// The reader's host clock now, the one behind `pose.host_timestamp_s`: right
+    /// after a `recv`, `host_now().as_secs_f64() - sample.pose.host_timestamp_s` is
+    /// how long the sample waited in the channel.
+    pub fn host_now(&self) -> Duration {
+        self.host_clock.now(self.epoch)
+    }
+
+    /// Stop the stream and wait for the reader thread to finish.
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    /// Like [`stop`](Self::stop), but wait at most `timeout` for the reader
+    /// thread, e.g. one stuck in a read on a wedged device. On
+    /// [`XvisioError::Timeout`] the thread is detached and exits on its own once
+    /// its read returns.
+    pub fn stop_timeout(mut self, timeout: Duration) -> Result<()> {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+        let deadline = Instant::now() + timeout;
+        while !thread.is_finished() {
+            if Instant::now() >= deadline {
+                log::warn!(
+                    "SLAM thread still running after {:?}, detaching it",
+                    timeout
+                );
+                // The detached thread may still be using the HidDevice.
+                std::mem::forget(self._api.take());
+                return Err(XvisioError::Timeout);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let _ = thread.join();
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Error for a closed channel, telling a watchdog stop apart from a normal one.
+fn stopped_error(stalled: &AtomicBool) -> XvisioError {
+    if stalled.load(Ordering::Relaxed) {
+        XvisioError::StreamStalled
+    } else {
+        XvisioError::StreamStopped
+    }
+}
+
+/// An extra consumer of a [`SlamStream`], see [`SlamStream::subscribe`].
+///
+/// Its channel closes when the stream stops; it can be moved to another thread.
+pub struct SlamReceiver {
+    receiver: Receiver<SlamSample>,
+    stalled: Arc<AtomicBool>,
+}
+
+impl SlamReceiver {
+    /// Receive the next SLAM sample (blocks until available).
+    pub fn recv(&self) -> Result<SlamSample> {
+        self.receiver
+            .recv()
+            .map_err(|_| stopped_error(&self.stalled))
+    }
+
+    /// Try to receive a SLAM sample without blocking.
+    pub fn try_recv(&self) -> Option<SlamSample> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Receive a SLAM sample with a timeout.
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<SlamSample> {
+        self.receiver.recv_timeout(timeout).map_err(|e| match e {
+            crossbeam_channel::RecvTimeoutError::Timeout => XvisioError::Timeout,
+            crossbeam_channel::RecvTimeoutError::Disconnected => stopped_error(&self.stalled),
+        })
+    }
+
+    /// Blocking iterator over samples, ending once the reader thread stops.
+    pub fn iter(&self) -> SlamIter<'_> {
+        SlamIter(self.receiver.iter())
+    }
+}
+
+impl<'a> IntoIterator for &'a SlamReceiver {
+    type Item = SlamSample;
+    type IntoIter = SlamIter<'a>;
+
+    fn into_iter(self) -> SlamIter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SlamStream {
+    type Item = SlamSample;
+    type IntoIter = SlamIter<'a>;
+
+    fn into_iter(self) -> SlamIter<'a> {
+        self.iter()
+    }
+}
+
+/// Blocking iterator over a [`SlamStream`], see [`SlamStream::iter`].
+pub struct SlamIter<'a>(crossbeam_channel::Iter<'a, SlamSample>);
+
+impl Iterator for SlamIter<'_> {
+    type Item = SlamSample;
+
+    fn next(&mut self) -> Option<SlamSample> {
+        self.0.next()
+    }
+}
+
+impl Drop for SlamStream {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Reader-thread side of a [`SlamStream`], shared by both backends.
+struct ReaderSink {
+    epoch: Instant,
+    samples: Sender<SlamSample>,
+    unparsed: Sender<RawRecord>,
+    events: Sender<StreamEvent>,
+    stop_flag: Arc<AtomicBool>,
+    capture: Option<CaptureWriter>,
+    #[cfg(all(feature = "shm", unix))]
+    shm: Option<crate::shm::ShmPublisher>,
+    output_divisor: u32,
+    /// Parsed samples so far, for decimation.
+    parsed: u32,
+    stall_timeout: Option<Duration>,
+    stop_on_stall: bool,
+    stalled: Arc<AtomicBool>,
+    last_timestamp_us: u64,
+    /// When the device timestamp last changed.
+    last_advance: Instant,
+    /// Signalled once on the first tracking sample, then dropped.
+    first_sample: Option<Sender<()>>,
+    first_tracking: Arc<OnceLock<Instant>>,
+    mount_transform: Option<Pose>,
+    tracking: Arc<AtomicU8>,
+    /// Identity poses seen before tracking started.
+    identity_samples: u32,
+    history: Option<Arc<Mutex<PoseHistory>>>,
+    read_timeout: Option<Duration>,
+    /// Last assigned sequence number and the device timestamp it was assigned to.
+    sequence: Option<(u64, u32)>,
+    stats: Arc<StatsCounters>,
+    subscribers: Arc<Mutex<Vec<Sender<SlamSample>>>>,
+    /// Consumer-side handle used to evict the oldest sample, with `keep_alive`.
+    evict: Option<Receiver<SlamSample>>,
+    host_elapsed: bool,
+    host_clock: HostClock,
+    pause_when_idle: Option<Duration>,
+    /// Since when every send found the sample channel full.
+    full_since: Option<Instant>,
+    gyro_bias: Option<GyroBiasEstimator>,
+    imu_calibration: Option<ImuCalibration>,
+    host_processor: Option<Arc<Mutex<dyn HostSlamProcessor>>>,
+    /// Header of SLAM reports, see [`SlamConfig::stream_header`].
+    header: [u8; 3],
+    /// Samples that reached the channel, for [`SlamConfig::max_samples`].
+    sent: u64,
+    max_samples: Option<u64>,
+    max_duration: Option<Duration>,
+    outliers: Option<OutlierFilter>,
+}
+
+impl ReaderSink {
+    /// Whether the reader should exit; ends the session first once
+    /// [`SlamConfig::max_duration`] has passed.
+    fn stopped(&self) -> bool {
+        if let Some(limit) = self.max_duration {
+            if self.epoch.elapsed() >= limit && !self.stop_flag.swap(true, Ordering::Relaxed) {
+                log::info!("SLAM session reached {:?}, stopping", limit);
+            }
+        }
+        self.stop_flag.load(Ordering::Relaxed)
+    }
+
+    fn stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    /// Publish `event`; dropped if nobody drains the event channel.
+    fn emit(&self, event: StreamEvent) {
+        let _ = self.events.try_send(event);
+    }
+
+    /// Stop the stream after the device dropped off the bus.
+    fn disconnected(&self) {
+        self.emit(StreamEvent::Disconnected);
+        self.stop();
+    }
+
+    /// Append a raw payload to the capture file; a failed write ends the capture.
+    fn record_raw(&mut self, data: &[u8]) {
+        if self.capture.is_none() {
+            return;
+        }
+        let host_timestamp_s = self.host_now().as_secs_f64();
+        if let Some(writer) = &mut self.capture {
+            if let Err(e) = writer.write(host_timestamp_s, data) {
+                log::warn!("Raw capture write failed, capture stopped: {}", e);
+                self.capture = None;
+            }
+        }
+    }
+
+    /// Host time of a sample received now, on the configured clock.
+    fn host_now(&self) -> Duration {
+        self.host_clock.now(self.epoch)
+    }
+
+    /// Parse and send a SLAM sample to the channel, or forward the report unparsed.
+    fn dispatch(&mut self, data: &[u8]) {
+        let elapsed = self.host_now();
+        match protocol::parse_sample(data) {
+            Some(sample) => {
+                self.stats.samples.fetch_add(1, Ordering::Relaxed);
+                self.deliver(sample, elapsed)
+            }
+            None => self.forward_unparsed(data),
+        }
+    }
+
+    /// Pass a non-edge report to the host processor; forward it unparsed if there
+    /// is none or it yields no pose.
+    fn process_on_host(&mut self, data: &[u8]) {
+        let elapsed = self.host_now();
+        let pose = self
+            .host_processor
+            .as_ref()
+            .and_then(|processor| processor.lock().ok()?.process(data));
+        match pose {
+            Some(pose) => self.deliver(
+                SlamSample {
+                    pose,
+                    imu: None,
+                    raw_extended: [0; 26],
+                    sequence: 0,
+                    host_elapsed: None,
+                },
+                elapsed,
+            ),
+            None => self.forward_unparsed(data),
+        }
+    }
+
+    /// Stamp, correct and track a parsed sample, then send it to the channel and
+    /// subscribers.
+    fn deliver(&mut self, mut sample: SlamSample, elapsed: Duration) {
+        sample.pose.host_timestamp_s = elapsed.as_secs_f64();
+        sample.host_elapsed = self.host_elapsed.then_some(elapsed);
+        if let (Some(calibration), Some(imu)) = (&self.imu_calibration, &mut sample.imu) {
+            calibration.apply(imu);
+        }
+        if let (Some(estimator), Some(imu)) = (&mut self.gyro_bias, &mut sample.imu) {
+            estimator.push(imu);
+            estimator.correct(imu);
+        }
+        sample.sequence = self.next_sequence(sample.pose.timestamp_us as u32);
+        if let Some(filter) = &mut self.outliers {
+            if !filter.accept(&sample.pose) {
+                self.stats.outliers.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        }
+        self.watch_clock(sample.pose.timestamp_us);
+        self.watch_tracking(&sample.pose);
+        if let Some(mount) = &self.mount_transform {
+            sample.pose = sample.pose.compose(mount);
+        }
+        if let Some(history) = &self.history {
+            if let Ok(mut history) = history.lock() {
+                history.push(sample.pose);
+            }
+        }
+        self.parsed = self.parsed.wrapping_add(1);
+        if !self.parsed.is_multiple_of(self.output_divisor) {
+            return;
+        }
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|s| {
+                !matches!(
+                    s.try_send(sample.clone()),
+                    Err(crossbeam_channel::TrySendError::Disconnected(_))
+                )
+            });
+        }
+        #[cfg(all(feature = "shm", unix))]
+        if let Some(shm) = &mut self.shm {
+            shm.publish(&sample.pose);
+        }
+        let delivered = match self.samples.try_send(sample) {
+            Ok(()) => {
+                self.full_since = None;
+                true
+            }
+            Err(e) => match e {
+                crossbeam_channel::TrySendError::Full(sample) => {
+                    log::trace!("SLAM channel full, dropping sample");
+                    self.stats.channel_drops.fetch_add(1, Ordering::Relaxed);
+                    let evicted = self.evict.as_ref().is_some_and(|evict| {
+                        let _ = evict.try_recv();
+                        self.samples.try_send(sample).is_ok()
+                    });
+                    if evicted {
+                        self.full_since = None;
+                    } else {
+                        self.full_since.get_or_insert_with(Instant::now);
+                    }
+                    evicted
+                }
+                crossbeam_channel::TrySendError::Disconnected(_) => {
+                    log::info!("SLAM channel disconnected, stopping reader");
+                    self.stop();
+                    false
+                }
+            },
+        };
+        if !delivered {
+            return;
+        }
+        self.sent += 1;
+        if self.max_samples == Some(self.sent) {
+            log::info!("SLAM session reached {} samples, stopping", self.sent);
+            self.stop();
+        }
+    }
+
+    /// With [`SlamConfig::pause_when_idle`], block while no consumer has room
+    /// once the channel has stayed full long enough.
+    fn wait_while_idle(&mut self) {
+        let (Some(after), Some(since)) = (self.pause_when_idle, self.full_since) else {
+            return;
+        };
+        if since.elapsed() < after || !self.idle() {
+            return;
+        }
+        log::info!("SLAM: no consumer for {:?}, pausing reads", after);
+        while self.idle() && !self.stopped() {
+            std::thread::sleep(IDLE_POLL_INTERVAL);
+        }
+        self.full_since = None;
+        log::info!("SLAM: consumer back, resuming reads");
+    }
+
+    /// Whether the sample channel and every subscriber are full. A rendezvous
+    /// channel always counts as full, so it never pauses.
+    fn idle(&self) -> bool {
+        self.samples.capacity() != Some(0)
+            && self.samples.is_full()
+            && self
+                .subscribers
+                .lock()
+                .is_ok_and(|subscribers| subscribers.iter().all(|s| s.is_full()))
+    }
+
+    /// Number the sample, skipping the packets a jump in the device clock implies
+    /// were lost. The u32 microsecond clock wraps after ~71 minutes; a step of
+    /// more than half that is taken as the clock going backwards (e.g. a device
+    /// reset) and resyncs without counting a gap.
+    fn next_sequence(&mut self, timestamp_us: u32) -> u64 {
+        let sequence = match self.sequence {
+            None => 0,
+            Some((last, last_timestamp_us)) => {
+                let dt = timestamp_us.wrapping_sub(last_timestamp_us);
+                if dt > u32::MAX / 2 {
+                    log::debug!(
+                        "SLAM device clock stepped back from {} to {} us, resyncing",
+                        last_timestamp_us,
+                        timestamp_us
+                    );
+                    self.sequence = Some((last + 1, timestamp_us));
+                    return last + 1;
+                }
+                let missed = ((dt + SAMPLE_PERIOD_US / 2) / SAMPLE_PERIOD_US).saturating_sub(1);
+                if missed > 0 {
+                    log::debug!("SLAM gap: {} us, ~{} packets lost", dt, missed);
+                    self.stats.gaps.fetch_add(1, Ordering::Relaxed);
+                    self.stats
+                        .missed_samples
+                        .fetch_add(missed as u64, Ordering::Relaxed);
+                }
+                last + 1 + missed as u64
+            }
+        };
+        self.sequence = Some((sequence, timestamp_us));
+        sequence
+    }
+
+    /// Flag a stall when the device clock stops advancing while reads still succeed.
+    fn watch_clock(&mut self, timestamp_us: u64) {
+        let Some(timeout) = self.stall_timeout else {
+            return;
+        };
+        if timestamp_us != self.last_timestamp_us {
+            self.last_timestamp_us = timestamp_us;
+            self.last_advance = Instant::now();
+            if self.stalled.swap(false, Ordering::Relaxed) {
+                self.emit(StreamEvent::Resumed);
+            }
+        } else if self.last_advance.elapsed() >= timeout
+            && !self.stalled.swap(true, Ordering::Relaxed)
+        {
+            log::warn!(
+                "SLAM stream stalled: device timestamp stuck at {} us",
+                timestamp_us
+            );
+            self.emit(StreamEvent::Stalled);
+            if self.stop_on_stall {
+                self.stop();
+            }
+        }
+    }
+
+    /// Track whether real poses have started, flagging cameras that never activate.
+    fn watch_tracking(&mut self, pose: &Pose) {
+        let status = TrackingStatus::from_u8(self.tracking.load(Ordering::Relaxed));
+        if status == TrackingStatus::Tracking {
+            return;
+        }
+        if !pose.is_identity() {
+            self.tracking
+                .store(TrackingStatus::Tracking as u8, Ordering::Relaxed);
+            let _ = self.first_tracking.set(Instant::now());
+            if let Some(ready) = self.first_sample.take() {
+                let _ = ready.send(());
+            }
+            return;
+        }
+        self.identity_samples += 1;
+        if self.identity_samples == CAMERAS_INACTIVE_SAMPLES {
+            log::warn!(
+                "SLAM: {} identity poses since start, cameras appear inactive",
+                CAMERAS_INACTIVE_SAMPLES
+            );
+            self.tracking
+                .store(TrackingStatus::CamerasInactive as u8, Ordering::Relaxed);
+            self.emit(StreamEvent::CamerasInactive);
+        }
+    }
+
+    /// Surface a report that isn't an edge SLAM packet; dropped if nobody reads them.
+    fn forward_unparsed(&self, data: &[u8]) {
+        if self.unparsed.is_full() {
+            return;
+        }
+        let _ = self.unparsed.try_send(RawRecord {
+            host_timestamp_s: self.host_now().as_secs_f64(),
+            data: data.to_vec(),
+        });
+    }
+}
+
+/// Resamples the ~950 Hz stream onto an evenly spaced display cadence.
+///
+/// Ticks are laid out on the device clock from the first sample on, so output is
+/// phase-aligned however irregularly samples reach the host. Each tick yields one
+/// sample whose pose is interpolated between the two samples around it (see
+/// [`Pose::interpolate`]); IMU and extended data come from the nearer one. Ticks
+/// a gap in the stream skipped over are dropped rather than delivered in a burst.
+/// `hz` should stay below the sample rate, since each input yields at most one tick.
+pub struct FixedRate {
+    period_us: f64,
+    interpolate: bool,
+    /// Device time of the first tick.
+    origin_us: Option<u32>,
+    /// Index of the next tick.
+    ticks: u64,
+    prev: Option<SlamSample>,
+}
 
-        let thread = std::thread::Builder::new()
-            .name("xvisio-slam".into())
-            .spawn(move || {
-                slam_reader_hidapi(device, sender, stop_clone);
-            })
-            .map_err(|e| XvisioError::HidCommand(format!("Failed to spawn SLAM thread: {}", e)))?;
+impl FixedRate {
+    pub fn new(hz: f64) -> Self {
+        Self {
+            period_us: 1e6 / hz.max(f64::MIN_POSITIVE),
+            interpolate: true,
+            origin_us: None,
+            ticks: 0,
+            prev: None,
+        }
+    }
 
-        Ok(SlamStream {
-            receiver,
-            stop_flag,
-            thread: Some(thread),
-            _api: Some(api),
-        })
+    /// Deliver the sample nearest each tick unchanged instead of interpolating.
+    pub fn interpolate(mut self, enabled: bool) -> Self {
+        self.interpolate = enabled;
+        self
     }
 
-    /// Start the SLAM streaming thread using rusb (macOS).
-    pub(crate) fn start_rusb(
-        handle: rusb::DeviceHandle<rusb::GlobalContext>,
-    ) -> Result<SlamStream> {
-        let (sender, receiver) = crossbeam_channel::bounded(256);
-        let stop_flag = Arc::new(AtomicBool::new(false));
-        let stop_clone = stop_flag.clone();
+    /// Feed the next sample; returns the output sample once a tick is reached.
+    pub fn push(&mut self, sample: SlamSample) -> Option<SlamSample> {
+        // The u32 microsecond clock wraps after ~71 minutes, hence wrapping offsets.
+        let ts = sample.pose.timestamp_us as u32;
+        let origin = *self.origin_us.get_or_insert(ts);
+        let tick_at =
+            |ticks: u64| origin.wrapping_add((ticks as f64 * self.period_us).round() as u64 as u32);
+        let tick = tick_at(self.ticks);
+        if (ts.wrapping_sub(tick) as i32) < 0 {
+            self.prev = Some(sample);
+            return None;
+        }
+        self.ticks += 1;
+        while ts.wrapping_sub(tick_at(self.ticks)) as i32 >= 0 {
+            self.ticks += 1;
+        }
 
-        let thread = std::thread::Builder::new()
-            .name("xvisio-slam".into())
-            .spawn(move || {
-                slam_reader_rusb(handle, sender, stop_clone);
-            })
-            .map_err(|e| XvisioError::HidCommand(format!("Failed to spawn SLAM thread: {}", e)))?;
+        let out = match self.prev.take() {
+            Some(prev) => {
+                let before = tick.wrapping_sub(prev.pose.timestamp_us as u32);
+                let span = ts.wrapping_sub(prev.pose.timestamp_us as u32);
+                let t = before as f64 / span.max(1) as f64;
+                let mut out = if t < 0.5 {
+                    prev.clone()
+                } else {
+                    sample.clone()
+                };
+                if self.interpolate {
+                    out.pose = prev.pose.interpolate(&sample.pose, t);
+                    out.pose.timestamp_us = tick as u64;
+                }
+                out
+            }
+            None => sample.clone(),
+        };
+        self.prev = Some(sample);
+        Some(out)
+    }
+}
 
-        Ok(SlamStream {
-            receiver,
-            stop_flag,
-            thread: Some(thread),
-            _api: None,
+/// What [`resample`] does with grid points outside the samples' time span.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GridEdge {
+    /// Repeat the first or last pose, stamped with the grid time.
+    #[default]
+    Clamp,
+    /// Leave those grid points out of the result.
+    Omit,
+}
+
+/// Poses at device times `start_us + k * step_us` for `k` in `0..count`, for
+/// offline alignment of a recording with other logs.
+///
+/// Each grid point interpolates between the two samples bracketing it with
+/// [`Pose::interpolate`]; recorded samples are ~1 ms apart, well within its
+/// accuracy. `samples` must be in ascending `timestamp_us` order, so a
+/// recording spanning a wrap of the 32-bit device clock (~71 min) has to be split.
+pub fn resample(
+    samples: &[SlamSample],
+    start_us: u64,
+    step_us: u64,
+    count: usize,
+    edge: GridEdge,
+) -> Vec<Pose> {
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return Vec::new();
+    };
+    (0..count as u64)
+        .filter_map(|k| {
+            let t = start_us + k * step_us;
+            let i = samples.partition_point(|s| s.pose.timestamp_us < t);
+            let pose = match (i.checked_sub(1).map(|i| &samples[i]), samples.get(i)) {
+                (_, Some(after)) if after.pose.timestamp_us == t => after.pose,
+                (Some(before), Some(after)) => {
+                    let (a, b) = (before.pose.timestamp_us, after.pose.timestamp_us);
+                    before
+                        .pose
+                        .interpolate(&after.pose, (t - a) as f64 / (b - a) as f64)
+                }
+                _ if edge == GridEdge::Omit => return None,
+                (None, _) => Pose {
+                    timestamp_us: t,
+                    ..first.pose
+                },
+                (Some(_), None) => Pose {
+                    timestamp_us: t,
+                    ..last.pose
+                },
+            };
+            Some(pose)
         })
+        .collect()
+}
+
+/// Linear velocity from the pose stream: translation differentiated over the
+/// device clock, then exponentially smoothed.
+///
+/// Differencing alone is mostly noise at ~950 Hz: one translation LSB (2^-14 m)
+/// between consecutive samples reads as 6 cm/s. The smoothing time constant
+/// trades that noise against lag; the default 20 ms averages about 20 samples.
+#[derive(Debug, Clone)]
+pub struct VelocityEstimator {
+    time_constant_s: f64,
+    last: Option<(u32, [f64; 3])>,
+    velocity: Option<[f64; 3]>,
+}
+
+impl Default for VelocityEstimator {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    /// Receive the next SLAM sample (blocks until available).
-    pub fn recv(&self) -> Result<SlamSample> {
-        self.receiver.recv().map_err(|_| XvisioError::StreamStopped)
+impl VelocityEstimator {
+    /// Device-clock gap after which differencing restarts instead of spanning
+    /// it, e.g. across a reconnect that began a new clock.
+    const MAX_GAP_US: u32 = 500_000;
+
+    /// Estimator with a 20 ms smoothing time constant.
+    pub fn new() -> Self {
+        Self {
+            time_constant_s: 0.02,
+            last: None,
+            velocity: None,
+        }
     }
 
-    /// Try to receive a SLAM sample without blocking.
-    pub fn try_recv(&self) -> Option<SlamSample> {
-        self.receiver.try_recv().ok()
+    /// Set the smoothing time constant; zero reports the raw differences.
+    pub fn time_constant(mut self, time_constant: Duration) -> Self {
+        self.time_constant_s = time_constant.as_secs_f64();
+        self
     }
 
-    /// Receive a SLAM sample with a timeout.
-    pub fn recv_timeout(&self, timeout: Duration) -> Result<SlamSample> {
-        self.receiver.recv_timeout(timeout).map_err(|e| match e {
-            crossbeam_channel::RecvTimeoutError::Timeout => XvisioError::Timeout,
-            crossbeam_channel::RecvTimeoutError::Disconnected => XvisioError::StreamStopped,
-        })
+    /// Add a pose and return the velocity in m/s in the pose's frame, `None`
+    /// until a second pose with a later timestamp arrives.
+    ///
+    /// The device clock is a u32 that wraps after ~71 minutes; differences are
+    /// taken modulo 2^32 so the wrap doesn't disturb the estimate.
+    pub fn push(&mut self, pose: &Pose) -> Option<[f64; 3]> {
+        let timestamp_us = pose.timestamp_us as u32;
+        let Some((last_us, last_translation)) = self.last else {
+            self.last = Some((timestamp_us, pose.translation));
+            return None;
+        };
+        let dt_us = timestamp_us.wrapping_sub(last_us);
+        if dt_us == 0 {
+            return self.velocity;
+        }
+        self.last = Some((timestamp_us, pose.translation));
+        if dt_us > Self::MAX_GAP_US {
+            self.velocity = None;
+            return None;
+        }
+        let dt = dt_us as f64 * 1e-6;
+        let alpha = dt / (self.time_constant_s + dt);
+        let mut velocity = self.velocity.unwrap_or([0.0; 3]);
+        for ((v, p), last) in velocity
+            .iter_mut()
+            .zip(pose.translation)
+            .zip(last_translation)
+        {
+            let raw = (p - last) / dt;
+            *v = match self.velocity {
+                Some(_) => *v + alpha * (raw - *v),
+                None => raw,
+            };
+        }
+        self.velocity = Some(velocity);
+        self.velocity
     }
 
-    /// Check if the stream is still active.
-    pub fn is_active(&self) -> bool {
-        !self.stop_flag.load(Ordering::Relaxed)
+    /// Current estimate, `None` before the second pose.
+    pub fn velocity(&self) -> Option<[f64; 3]> {
+        self.velocity
     }
+}
 
-    /// Stop the stream and wait for the reader thread to finish.
-    pub fn stop(mut self) {
-        self.shutdown();
+/// Reference state of [`SlamConfig::reject_outliers`].
+struct OutlierFilter {
+    limits: OutlierLimits,
+    last: Option<Pose>,
+    rejected: u32,
+}
+
+impl OutlierFilter {
+    /// Rejections in a row after which the next pose becomes the reference.
+    const MAX_REJECTED: u32 = 10;
+    /// Device-clock gap beyond which the reference is too old to judge by.
+    const MAX_GAP_US: u32 = 500_000;
+
+    fn new(limits: OutlierLimits) -> Self {
+        Self {
+            limits,
+            last: None,
+            rejected: 0,
+        }
     }
 
-    fn shutdown(&mut self) {
-        self.stop_flag.store(true, Ordering::Relaxed);
-        if let Some(thread) = self.thread.take() {
-            let _ = thread.join();
+    /// Whether `pose` is plausible after the last accepted pose; accepted poses
+    /// become the new reference.
+    fn accept(&mut self, pose: &Pose) -> bool {
+        if let Some(last) = &self.last {
+            let dt_us = (pose.timestamp_us as u32).wrapping_sub(last.timestamp_us as u32);
+            if self.rejected < Self::MAX_REJECTED && (1..=Self::MAX_GAP_US).contains(&dt_us) {
+                let dt = dt_us as f64 * 1e-6;
+                let distance = pose
+                    .translation
+                    .iter()
+                    .zip(last.translation)
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum::<f64>()
+                    .sqrt();
+                let dot: f64 = pose
+                    .quaternion
+                    .iter()
+                    .zip(last.quaternion)
+                    .map(|(a, b)| a * b)
+                    .sum();
+                let angle = 2.0 * dot.abs().min(1.0).acos();
+                if distance > self.limits.max_speed * dt
+                    || angle > self.limits.max_angular_speed * dt
+                {
+                    self.rejected += 1;
+                    return false;
+                }
+            }
         }
+        self.last = Some(*pose);
+        self.rejected = 0;
+        true
     }
 }
 
-impl Drop for SlamStream {
-    fn drop(&mut self) {
-        self.shutdown();
+/// Bounded ring of recent poses, oldest first.
+struct PoseHistory {
+    poses: VecDeque<Pose>,
+    depth: usize,
+}
+
+impl PoseHistory {
+    fn new(depth: usize) -> Self {
+        Self {
+            poses: VecDeque::with_capacity(depth),
+            depth,
+        }
+    }
+
+    fn push(&mut self, pose: Pose) {
+        if self.poses.len() == self.depth {
+            self.poses.pop_front();
+        }
+        self.poses.push_back(pose);
+    }
+
+    fn at(&self, timestamp_us: u64) -> Option<Pose> {
+        let i = self
+            .poses
+            .partition_point(|p| p.timestamp_us < timestamp_us);
+        let after = self.poses.get(i)?;
+        if after.timestamp_us == timestamp_us {
+            return Some(*after);
+        }
+        let before = self.poses.get(i.checked_sub(1)?)?;
+        let t = (timestamp_us - before.timestamp_us) as f64
+            / (after.timestamp_us - before.timestamp_us) as f64;
+        Some(before.interpolate(after, t))
     }
 }
 
-/// hidapi-based SLAM reader (Windows/Linux).
-fn slam_reader_hidapi(
-    device: hidapi::HidDevice,
-    sender: Sender<SlamSample>,
-    stop_flag: Arc<AtomicBool>,
-) {
-    let epoch = Instant::now();
-    let mut buf = [0u8; 64];
-    let debug_raw = std::env::var("XVISIO_DEBUG_RAW")
+fn debug_raw_enabled() -> bool {
+    std::env::var("XVISIO_DEBUG_RAW")
         .ok()
         .map(|v| {
             matches!(
@@ -125,207 +1855,603 @@ fn slam_reader_hidapi(
                 "1" | "true" | "yes" | "on"
             )
         })
-        .unwrap_or(false);
+        .unwrap_or(false)
+}
+
+/// Record, classify and hand off one buffer read by either reader.
+fn route_packet(
+    sink: &mut ReaderSink,
+    buf: &mut [u8],
+    len: usize,
+    debug_raw: bool,
+    debug_packets: &mut u32,
+) {
+    sink.record_raw(&buf[..len]);
+    let kind = protocol::classify_packet_with(buf, len, sink.header);
+    if debug_raw && *debug_packets < 20 {
+        *debug_packets += 1;
+        let (tag, data) = match &kind {
+            protocol::PacketKind::Slam(d) => ("", *d),
+            protocol::PacketKind::EchoPrefixed(d) => (" echo", *d),
+            protocol::PacketKind::Short(d) => (" short", *d),
+            protocol::PacketKind::Unexpected(d) => (" unexpected", *d),
+        };
+        let byte = |i: usize| data.get(i).copied().unwrap_or(0);
+        log::info!(
+            "SLAM raw[{}]: len={}{} hdr={:02x} {:02x} {:02x} ts={:02x}{:02x}{:02x}{:02x}",
+            debug_packets,
+            data.len(),
+            tag,
+            byte(0),
+            byte(1),
+            byte(2),
+            byte(6),
+            byte(5),
+            byte(4),
+            byte(3)
+        );
+    }
+    match kind {
+        protocol::PacketKind::Slam(data) | protocol::PacketKind::EchoPrefixed(data) => {
+            sink.dispatch(data)
+        }
+        protocol::PacketKind::Short(data) => {
+            let count = sink.stats.short_packets.fetch_add(1, Ordering::Relaxed) + 1;
+            if count <= 5 || count.is_multiple_of(1000) {
+                log::warn!(
+                    "SLAM short packet: {} bytes with a valid header ({} so far)",
+                    data.len(),
+                    count
+                );
+            }
+            sink.forward_unparsed(data);
+        }
+        protocol::PacketKind::Unexpected(data) => {
+            let count = sink.stats.unparsed_packets.fetch_add(1, Ordering::Relaxed) + 1;
+            if count <= 5 {
+                log::info!(
+                    "SLAM non-edge packet: len={} hdr={:02x?} ({} so far, see try_recv_unparsed)",
+                    data.len(),
+                    &data[..data.len().min(8)],
+                    count
+                );
+            }
+            sink.process_on_host(data);
+        }
+    }
+}
+
+/// hidapi-based SLAM reader (Windows/Linux).
+fn slam_reader_hidapi(device: hidapi::HidDevice, mut sink: ReaderSink) {
+    let mut buf = [0u8; 64];
+    let timeout_ms = sink
+        .read_timeout
+        .map_or(100, |t| t.as_millis().min(i32::MAX as u128) as i32);
+    let debug_raw = debug_raw_enabled();
     let mut debug_packets: u32 = 0;
 
     log::info!("SLAM reader started (hidapi)");
 
     loop {
-        if stop_flag.load(Ordering::Relaxed) {
+        if sink.stopped() {
             log::info!("SLAM reader stopping (stop flag set)");
             break;
         }
+        sink.wait_while_idle();
 
-        let len = match device.read_timeout(&mut buf, 100) {
+        let len = match device.read_timeout(&mut buf, timeout_ms) {
             Ok(0) => continue,
             Ok(n) => n,
             Err(e) => {
+                let e = XvisioError::from(e);
+                if e.is_disconnect() {
+                    log::error!("SLAM reader: device disconnected ({})", e);
+                    sink.disconnected();
+                    break;
+                }
                 log::warn!("SLAM read error: {}", e);
                 continue;
             }
         };
-
-        let data: &[u8] = if len >= protocol::REPORT_SIZE && buf[0] == protocol::SLAM_HEADER[0] {
-            &buf[..len]
-        } else {
-            if debug_raw && debug_packets < 20 {
-                debug_packets += 1;
-                let b0 = if len > 0 { buf[0] } else { 0 };
-                let b1 = if len > 1 { buf[1] } else { 0 };
-                let b2 = if len > 2 { buf[2] } else { 0 };
-                log::info!(
-                    "SLAM raw[{}]: len={} unexpected hdr={:02x} {:02x} {:02x}",
-                    debug_packets,
-                    len,
-                    b0,
-                    b1,
-                    b2
-                );
-            }
-            continue;
-        };
-
-        if debug_raw && debug_packets < 20 {
-            debug_packets += 1;
-            log::info!(
-                "SLAM raw[{}]: len={} hdr={:02x} {:02x} {:02x} ts={:02x}{:02x}{:02x}{:02x}",
-                debug_packets,
-                len,
-                data[0],
-                data[1],
-                data[2],
-                data[6],
-                data[5],
-                data[4],
-                data[3]
-            );
-        }
-        dispatch_sample(data, epoch, &sender, &stop_flag);
+        route_packet(&mut sink, &mut buf, len, debug_raw, &mut debug_packets);
     }
 }
 
-/// rusb-based SLAM reader (macOS).
+/// rusb-based SLAM reader (macOS). `restream` holds the timeout count and
+/// edge stream start command for [`SlamConfig::restream_on_stall`].
+#[cfg(feature = "usb")]
 fn slam_reader_rusb(
     handle: rusb::DeviceHandle<rusb::GlobalContext>,
-    sender: Sender<SlamSample>,
-    stop_flag: Arc<AtomicBool>,
+    mut sink: ReaderSink,
+    restream: Option<(u32, [u8; protocol::REPORT_SIZE])>,
 ) {
-    let epoch = Instant::now();
     let mut buf = [0u8; 64];
-    let timeout = Duration::from_millis(200);
+    let timeout = sink.read_timeout.unwrap_or(Duration::from_millis(200));
     let mut consecutive_errors: u32 = 0;
-    let debug_raw = std::env::var("XVISIO_DEBUG_RAW")
-        .ok()
-        .map(|v| {
-            matches!(
-                v.trim().to_ascii_lowercase().as_str(),
-                "1" | "true" | "yes" | "on"
-            )
-        })
-        .unwrap_or(false);
+    let mut consecutive_timeouts: u32 = 0;
+    let debug_raw = debug_raw_enabled();
     let mut debug_packets: u32 = 0;
 
     log::info!("SLAM reader started (rusb)");
 
     loop {
-        if stop_flag.load(Ordering::Relaxed) {
+        if sink.stopped() {
             log::info!("SLAM reader stopping (stop flag set)");
             break;
         }
+        sink.wait_while_idle();
 
         let len = match handle.read_interrupt(protocol::SLAM_ENDPOINT, &mut buf, timeout) {
             Ok(n) => {
                 consecutive_errors = 0;
+                consecutive_timeouts = 0;
                 n
             }
-            Err(rusb::Error::Timeout) => continue,
+            Err(rusb::Error::Timeout) => {
+                consecutive_timeouts += 1;
+                if let Some((limit, cmd)) = &restream {
+                    if consecutive_timeouts >= *limit {
+                        log::warn!(
+                            "SLAM reader: no data for {} reads, re-sending edge stream start",
+                            consecutive_timeouts
+                        );
+                        consecutive_timeouts = 0;
+                        let _ = crate::Device::send_hid_command_rusb(
+                            &handle,
+                            cmd,
+                            protocol::CMD_EDGE_STREAM,
+                            Duration::from_secs(2),
+                            "edge stream restart",
+                        );
+                    }
+                }
+                continue;
+            }
             Err(rusb::Error::NoDevice) => {
                 log::error!("SLAM reader: device disconnected");
-                stop_flag.store(true, Ordering::Relaxed);
+                sink.disconnected();
                 break;
             }
             Err(rusb::Error::Pipe) | Err(rusb::Error::Io) => {
                 consecutive_errors += 1;
-                if consecutive_errors <= 5 || consecutive_errors % 50 == 0 {
+                if consecutive_errors <= 5 || consecutive_errors.is_multiple_of(50) {
                     log::warn!("SLAM interrupt read recovery ({})", consecutive_errors);
                 }
                 handle.clear_halt(protocol::SLAM_ENDPOINT).ok();
                 std::thread::sleep(Duration::from_millis(10));
                 if consecutive_errors > 1000 {
                     log::error!("SLAM reader: too many recoverable errors, stopping");
-                    stop_flag.store(true, Ordering::Relaxed);
+                    sink.stop();
                     break;
                 }
                 continue;
             }
             Err(e) => {
                 consecutive_errors += 1;
-                if consecutive_errors <= 5 || consecutive_errors % 50 == 0 {
+                if consecutive_errors <= 5 || consecutive_errors.is_multiple_of(50) {
                     log::warn!("SLAM interrupt read error: {}", e);
                 }
                 std::thread::sleep(Duration::from_millis(10));
                 if consecutive_errors > 1000 {
                     log::error!("SLAM reader: too many consecutive errors, stopping");
-                    stop_flag.store(true, Ordering::Relaxed);
+                    sink.stop();
                     break;
                 }
                 continue;
             }
         };
-
-        // Interrupt transfers don't include the report ID — the data starts
-        // directly with the command echo bytes (0xA2, 0x33).
-        // Prepend the report ID (0x01) to match the expected SLAM packet format.
-        if len >= 2 && buf[0] == protocol::SLAM_HEADER[1] && buf[1] == protocol::SLAM_HEADER[2] {
-            // Shift data right by 1 and insert report ID
-            let total = (len + 1).min(64);
-            buf.copy_within(0..len, 1);
-            buf[0] = protocol::SLAM_HEADER[0]; // 0x01
-            if debug_raw && debug_packets < 20 {
-                debug_packets += 1;
-                log::info!(
-                    "SLAM raw[{}]: len={} hdr={:02x} {:02x} {:02x}",
-                    debug_packets,
-                    total,
-                    buf[0],
-                    buf[1],
-                    buf[2]
-                );
-            }
-            dispatch_sample(&buf[..total], epoch, &sender, &stop_flag);
-        } else if len >= protocol::REPORT_SIZE && buf[0] == protocol::SLAM_HEADER[0] {
-            // Report ID is included (some libusb configurations)
-            if debug_raw && debug_packets < 20 {
-                debug_packets += 1;
-                log::info!(
-                    "SLAM raw[{}]: len={} hdr={:02x} {:02x} {:02x}",
-                    debug_packets,
-                    len,
-                    buf[0],
-                    buf[1],
-                    buf[2]
-                );
-            }
-            dispatch_sample(&buf[..len], epoch, &sender, &stop_flag);
-        } else if debug_raw && debug_packets < 20 {
-            debug_packets += 1;
-            let b0 = if len > 0 { buf[0] } else { 0 };
-            let b1 = if len > 1 { buf[1] } else { 0 };
-            let b2 = if len > 2 { buf[2] } else { 0 };
-            log::info!(
-                "SLAM raw[{}]: len={} unexpected hdr={:02x} {:02x} {:02x}",
-                debug_packets,
-                len,
-                b0,
-                b1,
-                b2
-            );
-        }
+        route_packet(&mut sink, &mut buf, len, debug_raw, &mut debug_packets);
     }
 
     // Release interface — ignore errors (device may already be disconnected)
-    handle.release_interface(protocol::HID_INTERFACE as u8).ok();
+    handle.release_interface(protocol::HID_INTERFACE).ok();
     log::info!("SLAM reader stopped");
 }
 
-/// Parse and send a SLAM sample to the channel.
-fn dispatch_sample(
-    data: &[u8],
-    epoch: Instant,
-    sender: &Sender<SlamSample>,
-    stop_flag: &Arc<AtomicBool>,
-) {
-    if let Some(sample) = protocol::parse_slam_packet(data, epoch) {
-        if let Err(e) = sender.try_send(sample) {
-            match e {
-                crossbeam_channel::TrySendError::Full(_) => {
-                    log::trace!("SLAM channel full, dropping sample");
-                }
-                crossbeam_channel::TrySendError::Disconnected(_) => {
-                    log::info!("SLAM channel disconnected, stopping reader");
-                    stop_flag.store(true, Ordering::Relaxed);
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sink(config: &SlamConfig) -> ReaderSink {
+        ReaderSink {
+            epoch: Instant::now(),
+            samples: crossbeam_channel::bounded(1).0,
+            unparsed: crossbeam_channel::bounded(1).0,
+            events: crossbeam_channel::bounded(EVENT_CAPACITY).0,
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            capture: None,
+            #[cfg(all(feature = "shm", unix))]
+            shm: None,
+            output_divisor: 1,
+            parsed: 0,
+            stall_timeout: config.stall_timeout,
+            stop_on_stall: config.stop_on_stall,
+            stalled: Arc::new(AtomicBool::new(false)),
+            last_timestamp_us: 0,
+            last_advance: Instant::now(),
+            first_sample: None,
+            first_tracking: Arc::default(),
+            mount_transform: None,
+            tracking: Arc::new(AtomicU8::new(TrackingStatus::Pending as u8)),
+            identity_samples: 0,
+            history: None,
+            read_timeout: None,
+            sequence: None,
+            stats: Arc::default(),
+            subscribers: Arc::default(),
+            evict: None,
+            host_elapsed: config.host_elapsed,
+            host_clock: config.host_clock,
+            pause_when_idle: config.pause_when_idle,
+            full_since: None,
+            gyro_bias: None,
+            imu_calibration: None,
+            host_processor: config.host_processor.clone(),
+            header: protocol::SLAM_HEADER,
+            sent: 0,
+            max_samples: config.max_samples,
+            max_duration: config.max_duration,
+            outliers: None,
+        }
+    }
+
+    #[test]
+    fn test_host_processor_turns_reports_into_samples() {
+        let config = SlamConfig::new(SlamMode::Mixed).host_processor(|data: &[u8]| {
+            (data[0] == 0x05).then(|| {
+                let mut pose = Pose::new([1.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]);
+                pose.timestamp_us = 500;
+                pose
+            })
+        });
+        let (samples, receiver) = crossbeam_channel::bounded(1);
+        let (unparsed, unparsed_rx) = crossbeam_channel::bounded(1);
+        let mut sink = ReaderSink {
+            samples,
+            unparsed,
+            ..sink(&config)
+        };
+        let mut buf = [0u8; 64];
+        for first in [0x05, 0x06] {
+            buf[0] = first;
+            route_packet(&mut sink, &mut buf, protocol::REPORT_SIZE, false, &mut 0);
+        }
+        let sample = receiver.try_recv().unwrap();
+        assert_eq!(sample.pose.translation, [1.0, 0.0, 0.0]);
+        assert_eq!(sample.pose.timestamp_us, 500);
+        assert_eq!(unparsed_rx.try_recv().unwrap().data[0], 0x06);
+        assert_eq!(sink.stats.unparsed_packets.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_keep_alive_evicts_oldest() {
+        let (samples, receiver) = crossbeam_channel::bounded(1);
+        let mut sink = ReaderSink {
+            samples,
+            evict: Some(receiver.clone()),
+            ..sink(&SlamConfig::new(SlamMode::Edge))
+        };
+        let mut packet = [0u8; protocol::REPORT_SIZE];
+        packet[..3].copy_from_slice(&protocol::SLAM_HEADER);
+        for timestamp_us in [1000u32, 2000] {
+            packet[3..7].copy_from_slice(&timestamp_us.to_le_bytes());
+            sink.dispatch(&packet);
+        }
+        assert_eq!(receiver.try_recv().unwrap().pose.timestamp_us, 2000);
+        assert_eq!(sink.stats.channel_drops.load(Ordering::Relaxed), 1);
+        assert!(sink.full_since.is_none());
+    }
+
+    #[test]
+    fn test_pause_when_idle_waits_for_room() {
+        let (samples, receiver) = crossbeam_channel::bounded(1);
+        let config = SlamConfig::new(SlamMode::Edge).pause_when_idle(Duration::ZERO);
+        let mut sink = ReaderSink {
+            samples,
+            ..sink(&config)
+        };
+        let mut packet = [0u8; protocol::REPORT_SIZE];
+        packet[..3].copy_from_slice(&protocol::SLAM_HEADER);
+        sink.dispatch(&packet);
+        assert!(sink.full_since.is_none());
+        sink.dispatch(&packet);
+        assert!(sink.full_since.is_some() && sink.idle());
+
+        let reader = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            receiver.recv().unwrap()
+        });
+        sink.wait_while_idle();
+        assert!(!sink.idle());
+        reader.join().unwrap();
+    }
+
+    #[test]
+    fn test_subscribers_get_samples_until_dropped() {
+        let mut sink = sink(&SlamConfig::new(SlamMode::Edge));
+        let (first, first_rx) = crossbeam_channel::bounded(4);
+        let (second, second_rx) = crossbeam_channel::bounded(4);
+        sink.subscribers.lock().unwrap().extend([first, second]);
+        let mut packet = [0u8; protocol::REPORT_SIZE];
+        packet[..3].copy_from_slice(&protocol::SLAM_HEADER);
+
+        sink.dispatch(&packet);
+        assert!(first_rx.try_recv().is_ok());
+        drop(second_rx);
+        sink.dispatch(&packet);
+        assert!(first_rx.try_recv().is_ok());
+        assert_eq!(sink.subscribers.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_sequence_counts_gaps() {
+        let mut sink = sink(&SlamConfig::new(SlamMode::Edge));
+        assert_eq!(sink.next_sequence(10_000), 0);
+        assert_eq!(sink.next_sequence(11_060), 1);
+        // Three periods later: two packets lost.
+        assert_eq!(sink.next_sequence(14_210), 4);
+        // Across the u32 wrap.
+        sink.sequence = Some((4, u32::MAX - 500));
+        assert_eq!(sink.next_sequence(550), 5);
+        // The clock stepping back resyncs.
+        assert_eq!(sink.next_sequence(100), 6);
+        assert_eq!(sink.next_sequence(1_160), 7);
+        assert_eq!(sink.stats.gaps.load(Ordering::Relaxed), 1);
+        assert_eq!(sink.stats.missed_samples.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_watchdog_flags_frozen_clock() {
+        let config = SlamConfig::new(SlamMode::Edge)
+            .stall_timeout(Some(Duration::ZERO))
+            .stop_on_stall(true);
+        let mut sink = sink(&config);
+        sink.watch_clock(1000);
+        assert!(!sink.stalled.load(Ordering::Relaxed));
+        sink.watch_clock(1000);
+        assert!(sink.stalled.load(Ordering::Relaxed));
+        assert!(sink.stopped());
+        sink.watch_clock(2000);
+        assert!(!sink.stalled.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_cameras_inactive_after_identity_run() {
+        let mut sink = sink(&SlamConfig::new(SlamMode::Edge));
+        let identity = Pose::new([0.0; 3], [0.0, 0.0, 0.0, 1.0]);
+        for _ in 0..CAMERAS_INACTIVE_SAMPLES {
+            sink.watch_tracking(&identity);
+        }
+        let status =
+            |sink: &ReaderSink| TrackingStatus::from_u8(sink.tracking.load(Ordering::Relaxed));
+        assert_eq!(status(&sink), TrackingStatus::CamerasInactive);
+        sink.watch_tracking(&Pose::new([0.01, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]));
+        assert_eq!(status(&sink), TrackingStatus::Tracking);
+    }
+
+    #[test]
+    fn test_stats_report_queued_samples() {
+        let stream = SlamStream::spawn(&SlamConfig::new(SlamMode::Edge), None, |mut sink| {
+            let mut packet = [0u8; protocol::REPORT_SIZE];
+            packet[..3].copy_from_slice(&protocol::SLAM_HEADER);
+            for timestamp_us in [1000u32, 2000] {
+                packet[3..7].copy_from_slice(&timestamp_us.to_le_bytes());
+                sink.dispatch(&packet);
+            }
+        });
+        let mut stream = stream.unwrap();
+        stream.thread.take().unwrap().join().unwrap();
+        assert_eq!(stream.stats().queued, 2);
+        let sample = stream.recv().unwrap();
+        assert!(stream.host_now().as_secs_f64() >= sample.pose.host_timestamp_s);
+        assert_eq!(stream.stats().queued, 1);
+    }
+
+    #[test]
+    fn test_config_is_send() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+        assert_send::<SlamConfig>();
+        assert_sync::<SlamConfig>();
+        assert_send::<crate::PersistentStream>();
+    }
+
+    #[test]
+    fn test_max_samples_stops_reader() {
+        let timed = sink(&SlamConfig::new(SlamMode::Edge).max_duration(Duration::ZERO));
+        assert!(timed.stopped());
+
+        let (samples, receiver) = crossbeam_channel::bounded(8);
+        let mut sink = ReaderSink {
+            samples,
+            ..sink(&SlamConfig::new(SlamMode::Edge).max_samples(2))
+        };
+        let mut packet = [0u8; protocol::REPORT_SIZE];
+        packet[..3].copy_from_slice(&protocol::SLAM_HEADER);
+        for timestamp_us in [1000u32, 2000] {
+            assert!(!sink.stopped());
+            packet[3..7].copy_from_slice(&timestamp_us.to_le_bytes());
+            sink.dispatch(&packet);
+        }
+        assert!(sink.stopped());
+        assert_eq!(receiver.len(), 2);
+
+        let (samples, receiver) = crossbeam_channel::bounded(1);
+        let mut full = ReaderSink {
+            samples,
+            ..self::sink(&SlamConfig::new(SlamMode::Edge).max_samples(2))
+        };
+        for timestamp_us in [1000u32, 2000, 3000] {
+            packet[3..7].copy_from_slice(&timestamp_us.to_le_bytes());
+            full.dispatch(&packet);
+        }
+        assert!(!full.stopped());
+        receiver.recv().unwrap();
+        packet[3..7].copy_from_slice(&4000u32.to_le_bytes());
+        full.dispatch(&packet);
+        assert!(full.stopped());
+    }
+
+    #[test]
+    fn test_outlier_filter_drops_teleports() {
+        let pose = |t: u64, x: f64| {
+            let mut pose = Pose::new([x, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]);
+            pose.timestamp_us = t;
+            pose
+        };
+        let mut filter = OutlierFilter::new(OutlierLimits::default());
+        assert!(filter.accept(&pose(1000, 0.0)));
+        assert!(filter.accept(&pose(2000, 0.005)));
+        assert!(!filter.accept(&pose(3000, 3.0)));
+        assert!(filter.accept(&pose(4000, 0.01)));
+
+        let half_turn = Pose::new([0.01, 0.0, 0.0], [0.0, 0.0, 1.0, 0.0]);
+        assert!(!filter.accept(&Pose {
+            timestamp_us: 5000,
+            ..half_turn
+        }));
+        assert!(filter.accept(&pose(6000, 0.012)));
+        // A lasting jump becomes the new reference after MAX_REJECTED tries.
+        let accepted = (0..=OutlierFilter::MAX_REJECTED as u64)
+            .map(|k| filter.accept(&pose(7000 + k * 1000, 5.0)))
+            .collect::<Vec<_>>();
+        assert_eq!(accepted.iter().filter(|&&a| a).count(), 1);
+        assert!(accepted.last().unwrap());
+    }
+
+    #[test]
+    fn test_stall_events() {
+        let (events, event_rx) = crossbeam_channel::bounded(EVENT_CAPACITY);
+        let mut sink = ReaderSink {
+            events,
+            ..sink(&SlamConfig::new(SlamMode::Edge).stall_timeout(Some(Duration::ZERO)))
+        };
+        for timestamp_us in [1000, 1000, 1000, 2000] {
+            sink.watch_clock(timestamp_us);
+        }
+        let events: Vec<_> = event_rx.try_iter().collect();
+        assert_eq!(events, [StreamEvent::Stalled, StreamEvent::Resumed]);
+    }
+
+    #[test]
+    fn test_pose_history_interpolates() {
+        let mut history = PoseHistory::new(2);
+        for (t, x) in [(1000, 0.0), (2000, 1.0), (3000, 2.0)] {
+            let mut pose = Pose::new([x, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]);
+            pose.timestamp_us = t;
+            history.push(pose);
+        }
+        // Oldest sample evicted.
+        assert!(history.at(1500).is_none());
+        assert_eq!(history.at(2000).unwrap().translation[0], 1.0);
+        let mid = history.at(2250).unwrap();
+        assert!((mid.translation[0] - 1.25).abs() < 1e-12);
+        assert_eq!(mid.timestamp_us, 2250);
+        assert!(history.at(3001).is_none());
+    }
+
+    #[test]
+    fn test_fixed_rate_aligns_to_device_clock() {
+        let sample = |t: u64| {
+            let mut pose = Pose::new([t as f64 / 1000.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]);
+            pose.timestamp_us = t;
+            SlamSample {
+                pose,
+                imu: None,
+                raw_extended: [0; 26],
+                sequence: 0,
+                host_elapsed: None,
             }
+        };
+        let mut rate = FixedRate::new(100.0);
+        let out: Vec<_> = [0, 3000, 6000, 9000, 12_000, 15_000, 18_000, 21_000, 45_000]
+            .into_iter()
+            .filter_map(|t| rate.push(sample(t)))
+            .map(|s| (s.pose.timestamp_us, s.pose.translation[0]))
+            .collect();
+        // The gap to 45 ms yields tick 30 only; tick 40 is dropped.
+        assert_eq!(out.len(), 4);
+        for (&(t, x), expected) in out.iter().zip([0u64, 10_000, 20_000, 30_000]) {
+            assert_eq!(t, expected);
+            assert!((x - expected as f64 / 1000.0).abs() < 1e-9);
         }
+
+        let mut nearest = FixedRate::new(100.0).interpolate(false);
+        let out: Vec<_> = [0, 6000, 11_000]
+            .into_iter()
+            .filter_map(|t| nearest.push(sample(t)))
+            .map(|s| s.pose.timestamp_us)
+            .collect();
+        assert_eq!(out, [0, 11_000]);
+    }
+
+    #[test]
+    fn test_resample_grid_edges() {
+        let samples: Vec<_> = [1000, 2000, 4000]
+            .into_iter()
+            .map(|t| {
+                let mut pose = Pose::new([t as f64 / 1000.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]);
+                pose.timestamp_us = t;
+                SlamSample {
+                    pose,
+                    imu: None,
+                    raw_extended: [0; 26],
+                    sequence: 0,
+                    host_elapsed: None,
+                }
+            })
+            .collect();
+        let clamped = resample(&samples, 0, 1500, 4, GridEdge::Clamp);
+        let points: Vec<_> = clamped
+            .iter()
+            .map(|p| (p.timestamp_us, p.translation[0]))
+            .collect();
+        assert_eq!(points, [(0, 1.0), (1500, 1.5), (3000, 3.0), (4500, 4.0)]);
+
+        let omitted = resample(&samples, 0, 1500, 4, GridEdge::Omit);
+        let times: Vec<_> = omitted.iter().map(|p| p.timestamp_us).collect();
+        assert_eq!(times, [1500, 3000]);
+        assert!(resample(&[], 0, 1000, 3, GridEdge::Clamp).is_empty());
+    }
+
+    #[test]
+    fn test_velocity_across_clock_wrap() {
+        let pose = |k: u64| {
+            // 1 m/s along x, sampled every 1 ms, with a sign-flipped jitter on y.
+            let jitter = if k.is_multiple_of(2) { 1e-4 } else { -1e-4 };
+            let mut pose = Pose::new([k as f64 * 1e-3, jitter, 0.0], [0.0, 0.0, 0.0, 1.0]);
+            pose.timestamp_us = (u32::MAX as u64 - 50_000 + k * 1000) & u32::MAX as u64;
+            pose
+        };
+        let mut estimator = VelocityEstimator::new();
+        assert!(estimator.push(&pose(0)).is_none());
+        let velocity = (1..=100).filter_map(|k| estimator.push(&pose(k))).last();
+        let [vx, vy, vz] = velocity.unwrap();
+        assert!((vx - 1.0).abs() < 1e-6);
+        // Raw differences of the jitter are ±0.2 m/s.
+        assert!(vy.abs() < 0.02 && vz == 0.0);
+
+        let mut gap = pose(0);
+        gap.timestamp_us = gap.timestamp_us.wrapping_add(1_000_000) & u32::MAX as u64;
+        assert!(estimator.push(&gap).is_none());
+    }
+
+    #[test]
+    fn test_mac_backend_parse() {
+        assert_eq!(MacBackend::parse("rusb"), Some(MacBackend::Rusb));
+        assert_eq!(
+            MacBackend::parse(" HIDAPI "),
+            Some(MacBackend::IokitNoDetach)
+        );
+        assert_eq!(MacBackend::parse("iokit"), Some(MacBackend::IokitNoDetach));
+        assert_eq!(MacBackend::parse("usb"), None);
+    }
+
+    #[test]
+    fn test_watchdog_disabled() {
+        let mut sink = sink(&SlamConfig::new(SlamMode::Edge).stall_timeout(None));
+        sink.watch_clock(0);
+        sink.watch_clock(0);
+        assert!(!sink.stalled.load(Ordering::Relaxed));
     }
 }
@@ -1,3 +1,8 @@
+use crate::math::{atan2, sqrt};
+use crate::protocol::{quaternion_to_euler, quaternion_to_rotation, POSE_SIZE};
+use alloc::string::String;
+use core::time::Duration;
+
 /// 6DOF pose from the XR50 edge SLAM.
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -10,23 +15,263 @@ pub struct Pose {
     pub quaternion: [f64; 4],
     /// Edge timestamp in microseconds.
     pub timestamp_us: u64,
-    /// Host steady-clock timestamp in seconds.
+    /// Host timestamp in seconds: since the stream started by default, or since
+    /// the Unix epoch with `SlamConfig::host_clock(HostClock::SystemRealtime)`.
     pub host_timestamp_s: f64,
-    /// Tracking confidence [0..1]. Derived from extended packet data.
+    /// [`confidence_raw`](Self::confidence_raw) scaled by 2^-14 and clamped to [0, 1].
+    ///
+    /// Not a graded quality metric: the firmware meaning of the word is unknown. A
+    /// tracking device reads a constant 16683 (1.0 after clamping), one whose cameras
+    /// never started reads near 0. Use it as a tracking/not-tracking hint at most.
     pub confidence: f64,
     /// Euler angles [roll, pitch, yaw] in degrees (YXZ order with Z-flip for Three.js).
     /// roll = head tilt (Euler.z), pitch = look up/down (Euler.x), yaw = turn left/right (Euler.y).
     pub euler_deg: [f64; 3],
+    /// Unscaled int16 at packet bytes [57..58] that `confidence` is derived from.
+    pub confidence_raw: i16,
+    /// How the rotation payload was decoded for this sample.
+    pub rotation_source: RotationSource,
+}
+
+/// Interpretation of the rotation payload at packet bytes [19..36].
+///
+/// Unless `XVISIO_ROTATION_PARSE` forces a format, each packet is tried as a
+/// matrix first; a source that changes within a session points at a packet the
+/// heuristic misread.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationSource {
+    /// 3x3 int16 rotation matrix.
+    Matrix = 0,
+    /// Quaternion [w, x, y, z] in the first 8 bytes.
+    Quaternion = 1,
+}
+
+impl Pose {
+    /// Pose from a translation and a unit quaternion [qx, qy, qz, qw], with
+    /// zero timestamps and confidence. Handy for fixed offsets such as a mount transform.
+    pub fn new(translation: [f64; 3], quaternion: [f64; 4]) -> Pose {
+        let [qx, qy, qz, qw] = quaternion;
+        Pose {
+            translation,
+            rotation: quaternion_to_rotation(qw, qx, qy, qz),
+            quaternion,
+            timestamp_us: 0,
+            host_timestamp_s: 0.0,
+            confidence: 0.0,
+            euler_deg: quaternion_to_euler(qw, qx, qy, qz),
+            confidence_raw: 0,
+            rotation_source: RotationSource::Quaternion,
+        }
+    }
+
+    /// Apply `offset`, expressed in this pose's frame: `self * offset`.
+    /// Timestamps, confidence and rotation source are kept from `self`.
+    pub fn compose(&self, offset: &Pose) -> Pose {
+        let [x1, y1, z1, w1] = self.quaternion;
+        let [x2, y2, z2, w2] = offset.quaternion;
+        let quaternion = [
+            w1 * x2 + x1 * w2 + y1 * z2 - z1 * y2,
+            w1 * y2 - x1 * z2 + y1 * w2 + z1 * x2,
+            w1 * z2 + x1 * y2 - y1 * x2 + z1 * w2,
+            w1 * w2 - x1 * x2 - y1 * y2 - z1 * z2,
+        ];
+        let mut translation = self.translation;
+        for (t, row) in translation.iter_mut().zip(&self.rotation) {
+            *t += row
+                .iter()
+                .zip(&offset.translation)
+                .map(|(r, o)| r * o)
+                .sum::<f64>();
+        }
+        Pose {
+            timestamp_us: self.timestamp_us,
+            host_timestamp_s: self.host_timestamp_s,
+            confidence: self.confidence,
+            confidence_raw: self.confidence_raw,
+            rotation_source: self.rotation_source,
+            ..Pose::new(translation, quaternion)
+        }
+    }
+
+    /// True if no field is NaN or infinite.
+    pub fn is_finite(&self) -> bool {
+        self.translation
+            .iter()
+            .chain(self.rotation.iter().flatten())
+            .chain(&self.quaternion)
+            .chain(&self.euler_deg)
+            .chain([&self.host_timestamp_s, &self.confidence])
+            .all(|v| v.is_finite())
+    }
+
+    /// True for the origin pose the device reports before tracking has started,
+    /// e.g. when its cameras never activated.
+    pub fn is_identity(&self) -> bool {
+        let [qx, qy, qz, qw] = self.quaternion;
+        self.translation.iter().all(|t| t.abs() <= 1e-6)
+            && (qw.abs() - 1.0).abs() <= 0.01
+            && [qx, qy, qz].iter().all(|c| c.abs() <= 0.01)
+    }
+
+    /// Rotation as a unit axis and an angle in radians within [0, π], from the
+    /// quaternion. Near identity the axis is undefined, so `[1, 0, 0]` comes
+    /// back with the tiny angle rather than an axis amplified from noise.
+    pub fn axis_angle(&self) -> ([f64; 3], f64) {
+        let [x, y, z, w] = self.quaternion;
+        // q and -q are the same rotation; w >= 0 keeps the angle at most π.
+        let sign = if w < 0.0 { -1.0 } else { 1.0 };
+        let sin_half = sqrt(x * x + y * y + z * z);
+        let angle = 2.0 * atan2(sin_half, sign * w);
+        if sin_half < 1e-12 {
+            return ([1.0, 0.0, 0.0], angle);
+        }
+        let k = sign / sin_half;
+        ([x * k, y * k, z * k], angle)
+    }
+
+    /// Re-express this pose (given in the XR50 device frame) in `frame`.
+    ///
+    /// Translation, rotation and quaternion are mirrored as needed; `euler_deg`
+    /// is kept as is since it is always in the Three.js convention.
+    pub fn to_frame(&self, frame: CoordinateFrame) -> Pose {
+        let s = frame.axis_signs();
+        let det = s[0] * s[1] * s[2];
+        let mut pose = *self;
+        for i in 0..3 {
+            pose.translation[i] *= s[i];
+            pose.quaternion[i] *= det * s[i];
+            for j in 0..3 {
+                pose.rotation[i][j] *= s[i] * s[j];
+            }
+        }
+        pose
+    }
+
+    /// Blend towards `other` by `t` in [0, 1]: translation, timestamps and confidence
+    /// linearly, rotation by normalized quaternion lerp along the shorter arc.
+    /// `confidence_raw` and `rotation_source` come from whichever pose is nearer.
+    ///
+    /// Nlerp differs from slerp by well under a millidegree for poses one sample
+    /// (~1 ms) apart, which is what this is meant for.
+    pub fn interpolate(&self, other: &Pose, t: f64) -> Pose {
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+        let sign = if dot4(&self.quaternion, &other.quaternion) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        let mut quaternion = [0.0; 4];
+        for (i, q) in quaternion.iter_mut().enumerate() {
+            *q = lerp(self.quaternion[i], sign * other.quaternion[i]);
+        }
+        let quaternion = normalize4(quaternion).unwrap_or(self.quaternion);
+        let mut translation = [0.0; 3];
+        for (i, p) in translation.iter_mut().enumerate() {
+            *p = lerp(self.translation[i], other.translation[i]);
+        }
+        let dt = other.timestamp_us as f64 - self.timestamp_us as f64;
+        Pose {
+            timestamp_us: (self.timestamp_us as f64 + dt * t + 0.5) as u64,
+            host_timestamp_s: lerp(self.host_timestamp_s, other.host_timestamp_s),
+            confidence: lerp(self.confidence, other.confidence),
+            confidence_raw: if t < 0.5 { self } else { other }.confidence_raw,
+            rotation_source: if t < 0.5 { self } else { other }.rotation_source,
+            ..Pose::new(translation, quaternion)
+        }
+    }
+
+    /// Average a set of poses, e.g. samples from a stationary device used as a
+    /// calibration reference. Returns `None` for an empty slice.
+    ///
+    /// Translation, timestamps and confidence are arithmetic means; `confidence_raw`
+    /// and `rotation_source` are taken from the first pose. Rotation uses Markley's
+    /// method: the dominant eigenvector of Σ q·qᵀ, which is immune to the q/−q sign
+    /// ambiguity and to gimbal lock, unlike averaging components or Euler angles.
+    pub fn average(samples: &[Pose]) -> Option<Pose> {
+        let n = samples.len() as f64;
+        let first = samples.first()?;
+
+        let mut translation = [0.0; 3];
+        let mut outer = [[0.0; 4]; 4];
+        // Hemisphere-aligned sum: a good starting vector for the power iteration.
+        let mut aligned = [0.0; 4];
+        for pose in samples {
+            let q = pose.quaternion;
+            let sign = if dot4(&q, &first.quaternion) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            for i in 0..4 {
+                aligned[i] += sign * q[i];
+                for j in 0..4 {
+                    outer[i][j] += q[i] * q[j];
+                }
+            }
+            for (t, p) in translation.iter_mut().zip(pose.translation) {
+                *t += p / n;
+            }
+        }
+
+        let mut q = normalize4(aligned)?;
+        for _ in 0..32 {
+            let mut next = [0.0; 4];
+            for (i, row) in outer.iter().enumerate() {
+                next[i] = dot4(row, &q);
+            }
+            q = normalize4(next)?;
+        }
+
+        let [qx, qy, qz, qw] = q;
+        let timestamp_sum: u128 = samples.iter().map(|p| p.timestamp_us as u128).sum();
+        Some(Pose {
+            translation,
+            rotation: quaternion_to_rotation(qw, qx, qy, qz),
+            quaternion: q,
+            timestamp_us: (timestamp_sum / samples.len() as u128) as u64,
+            host_timestamp_s: samples.iter().map(|p| p.host_timestamp_s).sum::<f64>() / n,
+            confidence: samples.iter().map(|p| p.confidence).sum::<f64>() / n,
+            euler_deg: quaternion_to_euler(qw, qx, qy, qz),
+            confidence_raw: first.confidence_raw,
+            rotation_source: first.rotation_source,
+        })
+    }
+}
+
+fn dot4(a: &[f64; 4], b: &[f64; 4]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalize4(v: [f64; 4]) -> Option<[f64; 4]> {
+    let norm = sqrt(dot4(&v, &v));
+    (norm > 1e-12).then(|| v.map(|c| c / norm))
 }
 
 /// Raw IMU data parsed from extended SLAM packet bytes [37..48].
+///
+/// One reading per packet, so it shares the pose's `timestamp_us`; no separate,
+/// faster IMU report is known (see PROTOCOL.md). Decimating the stream with
+/// `SlamConfig::output_divisor` drops IMU readings along with the poses.
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct ImuData {
     /// Accelerometer [x, y, z] in g (hypothesis: scale = 2^-14 per g).
     pub accelerometer: [f64; 3],
     /// Gyroscope [x, y, z] in scaled units (hypothesis: rad/s).
     pub gyroscope: [f64; 3],
+    /// Per accelerometer axis: the raw int16 sat at its min or max, so the reading
+    /// is clipped (±2g under the scale hypothesis) rather than real.
+    pub accel_saturated: [bool; 3],
+    /// Per gyroscope axis: the raw int16 sat at its min or max.
+    pub gyro_saturated: [bool; 3],
+}
+
+impl ImuData {
+    /// Whether any axis of either sensor is clipped.
+    pub fn is_saturated(&self) -> bool {
+        self.accel_saturated.contains(&true) || self.gyro_saturated.contains(&true)
+    }
 }
 
 /// Full SLAM sample including pose, optional IMU, and raw extended data.
@@ -36,6 +281,38 @@ pub struct SlamSample {
     pub imu: Option<ImuData>,
     /// Raw bytes [37..62] from the SLAM packet for user analysis.
     pub raw_extended: [u8; 26],
+    /// Position in the stream. The packet carries no counter, so the reader derives
+    /// it from the device clock: it skips ahead by the packets a gap implies were
+    /// lost. 0 for samples parsed outside a stream.
+    pub sequence: u64,
+    /// Exact host time, the value `pose.host_timestamp_s` rounds to f64 seconds
+    /// (same clock, see `SlamConfig::host_clock`). Set only with
+    /// [`SlamConfig::host_elapsed`].
+    ///
+    /// [`SlamConfig::host_elapsed`]: crate::SlamConfig::host_elapsed
+    pub host_elapsed: Option<Duration>,
+}
+
+/// Typed views of `raw_extended`, by packet byte offset as in PROTOCOL.md.
+impl SlamSample {
+    /// Little-endian i16 at packet `offset` (37..=61); `None` outside the extended data.
+    pub fn extended_i16(&self, offset: usize) -> Option<i16> {
+        let i = offset.checked_sub(POSE_SIZE)?;
+        let b = self.raw_extended.get(i..i + 2)?;
+        Some(i16::from_le_bytes([b[0], b[1]]))
+    }
+
+    /// Raw word at bytes [57..58], the same value as `pose.confidence_raw`.
+    /// Constant on a stationary device, so it may be a status field instead.
+    pub fn status_word(&self) -> i16 {
+        self.extended_i16(57).unwrap_or(0)
+    }
+
+    /// Undecoded words at bytes [49..56]. The first has always read zero, the
+    /// rest are small noisy values; their meaning is unconfirmed.
+    pub fn unknown_words(&self) -> [i16; 4] {
+        [49, 51, 53, 55].map(|offset| self.extended_i16(offset).unwrap_or(0))
+    }
 }
 
 /// Device identification and capabilities.
@@ -43,9 +320,109 @@ pub struct SlamSample {
 pub struct DeviceInfo {
     pub uuid: String,
     pub version: String,
+    /// Feature bitmap as reported, including bits without a named flag.
     pub features: Features,
+    /// Platform HID path of the command collection.
     pub bus_id: String,
+    /// USB bus number (0 if it couldn't be determined).
+    pub bus_number: u8,
+    /// USB device address on that bus (0 if it couldn't be determined).
     pub device_address: u8,
+    /// HID usage page of the selected command collection (0 if the backend doesn't report it).
+    pub usage_page: u16,
+    /// HID usage of the selected command collection.
+    pub usage: u16,
+    /// USB serial number string (the printed serial), distinct from the firmware
+    /// [`uuid`](Self::uuid). `None` if the device or backend doesn't report one.
+    pub serial_number: Option<String>,
+    /// Set when the device is present but couldn't be queried; identity fields are then empty.
+    pub error: Option<String>,
+}
+
+impl DeviceInfo {
+    /// Firmware version parsed from [`version`](Self::version), if recognizable.
+    pub fn parsed_version(&self) -> Option<FirmwareVersion> {
+        FirmwareVersion::parse(&self.version)
+    }
+}
+
+/// Comparable firmware version, ordered by major, minor, then patch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FirmwareVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl FirmwareVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parse a firmware string such as `1V1.04P31||xr50|V1.09|...` (→ 1.4.31)
+    /// or a plain `2.1.0`.
+    ///
+    /// The first `V<major>.<minor>` wins; the patch follows as `P<n>` or `.<n>`
+    /// and defaults to 0.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.find('V').map_or(s, |i| &s[i + 1..]);
+        let (major, rest) = split_number(s)?;
+        let (minor, rest) = split_number(rest.strip_prefix('.')?)?;
+        let patch = rest
+            .strip_prefix(['.', 'P'])
+            .and_then(split_number)
+            .map_or(0, |(patch, _)| patch);
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl core::fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Split leading decimal digits off `s`.
+fn split_number(s: &str) -> Option<(u16, &str)> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    Some((s[..end].parse().ok()?, &s[end..]))
+}
+
+/// Runtime state probed from an opened device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceState {
+    /// SLAM packets are arriving on the interrupt endpoint.
+    pub streaming: bool,
+}
+
+/// Per-check results of [`Device::self_test`](crate::Device::self_test).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfTestReport {
+    /// UUID, version and features read back and match the values from open.
+    pub identity: bool,
+    /// At least one SLAM sample arrived.
+    pub streaming: bool,
+    /// Sample rate measured from the first sample on (nominally ~950 Hz).
+    pub sample_rate_hz: f64,
+    /// `sample_rate_hz` is plausible.
+    pub rate_ok: bool,
+    /// A tracking (non-identity) pose arrived, i.e. the cameras feed the SLAM.
+    pub cameras: bool,
+    /// Mean accelerometer magnitude in g, `None` if no sample carried IMU data.
+    pub accel_g: Option<f64>,
+    /// `accel_g` is near 1 g, as expected for a device at rest.
+    pub accel_ok: bool,
+}
+
+impl SelfTestReport {
+    /// All checks passed.
+    pub fn passed(&self) -> bool {
+        self.identity && self.streaming && self.rate_ok && self.cameras && self.accel_ok
+    }
 }
 
 bitflags::bitflags! {
@@ -65,12 +442,199 @@ bitflags::bitflags! {
     }
 }
 
-/// SLAM operating mode.
+impl Features {
+    /// Reported bits that have no named flag yet; `bits()` is the raw device value.
+    pub fn unknown_bits(&self) -> u32 {
+        self.bits() & !Features::all().bits()
+    }
+}
+
+/// Target coordinate system for [`Pose::to_frame`].
+///
+/// The XR50 reports X right, Y up, Z forward.
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateFrame {
+    /// Native XR50 axes, unchanged.
+    Device = 0,
+    /// Right-handed, Y up, -Z forward: OpenXR, OpenGL and Three.js.
+    OpenXr = 1,
+    /// Left-handed, Y up, Z forward: Unity. Matches the device axes.
+    Unity = 2,
+}
+
+impl CoordinateFrame {
+    /// Per-axis sign mapping device axes onto this frame.
+    fn axis_signs(self) -> [f64; 3] {
+        match self {
+            CoordinateFrame::Device | CoordinateFrame::Unity => [1.0, 1.0, 1.0],
+            CoordinateFrame::OpenXr => [1.0, 1.0, -1.0],
+        }
+    }
+}
+
+/// Tait-Bryan rotation order for [`quaternion_to_euler_order`](crate::protocol::quaternion_to_euler_order).
+/// `Yxz` means R = R_Y · R_X · R_Z, the Three.js naming.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EulerOrder {
+    Xyz,
+    Xzy,
+    Yxz,
+    Yzx,
+    Zxy,
+    Zyx,
+}
+
+impl EulerOrder {
+    /// Axis indices (0 = X, 1 = Y, 2 = Z) in application order.
+    pub(crate) fn axes(self) -> [usize; 3] {
+        match self {
+            EulerOrder::Xyz => [0, 1, 2],
+            EulerOrder::Xzy => [0, 2, 1],
+            EulerOrder::Yxz => [1, 0, 2],
+            EulerOrder::Yzx => [1, 2, 0],
+            EulerOrder::Zxy => [2, 0, 1],
+            EulerOrder::Zyx => [2, 1, 0],
+        }
+    }
+}
+
+/// SLAM operating mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SlamMode {
     /// On-device SLAM processing (edge6dof=1, embeddedAlgo=0).
-    Edge = 0,
+    Edge,
     /// Mixed host+device SLAM processing (edge6dof=0, embeddedAlgo=1).
-    Mixed = 1,
+    Mixed,
+    /// Raw configure-command parameters, for combinations the presets don't cover.
+    /// `uvc_mode` overrides `XVISIO_UVC_MODE`.
+    Custom {
+        edge: bool,
+        uvc_mode: u8,
+        embedded_algo: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pose(translation: [f64; 3], quaternion: [f64; 4]) -> Pose {
+        Pose {
+            translation,
+            rotation: [[0.0; 3]; 3],
+            quaternion,
+            timestamp_us: 0,
+            host_timestamp_s: 0.0,
+            confidence: 1.0,
+            euler_deg: [0.0; 3],
+            confidence_raw: 16384,
+            rotation_source: RotationSource::Matrix,
+        }
+    }
+
+    #[test]
+    fn test_extended_accessors() {
+        let mut raw_extended = [0u8; 26];
+        raw_extended[51 - 37..53 - 37].copy_from_slice(&3i16.to_le_bytes());
+        raw_extended[57 - 37..59 - 37].copy_from_slice(&16683i16.to_le_bytes());
+        let sample = SlamSample {
+            pose: Pose::new([0.0; 3], [0.0, 0.0, 0.0, 1.0]),
+            imu: None,
+            raw_extended,
+            sequence: 0,
+            host_elapsed: None,
+        };
+        assert_eq!(sample.unknown_words(), [0, 3, 0, 0]);
+        assert_eq!(sample.status_word(), 16683);
+        assert_eq!(sample.extended_i16(36), None);
+        assert_eq!(sample.extended_i16(62), None);
+    }
+
+    #[test]
+    fn test_is_finite() {
+        let mut p = Pose::new([1.0, 2.0, 3.0], [0.0, 0.0, 0.0, 1.0]);
+        assert!(p.is_finite());
+        p.rotation[2][1] = f64::NAN;
+        assert!(!p.is_finite());
+    }
+
+    #[test]
+    fn test_compose_mount_offset() {
+        let h = core::f64::consts::FRAC_1_SQRT_2;
+        // Device yawed 90° about Z, rig mounted 1 m along the device's X axis.
+        let device = Pose::new([1.0, 2.0, 3.0], [0.0, 0.0, h, h]);
+        let mount = Pose::new([1.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]);
+        let rig = device.compose(&mount);
+        for (got, want) in rig.translation.iter().zip([1.0, 3.0, 3.0]) {
+            assert!((got - want).abs() < 1e-9);
+        }
+        for (got, want) in rig.quaternion.iter().zip(device.quaternion) {
+            assert!((got - want).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_axis_angle() {
+        let h = core::f64::consts::FRAC_1_SQRT_2;
+        // 90° about Z, also given as the negated quaternion.
+        for q in [[0.0, 0.0, h, h], [0.0, 0.0, -h, -h]] {
+            let (axis, angle) = Pose::new([0.0; 3], q).axis_angle();
+            assert!((angle - core::f64::consts::FRAC_PI_2).abs() < 1e-12);
+            for (got, want) in axis.iter().zip([0.0, 0.0, 1.0]) {
+                assert!((got - want).abs() < 1e-12);
+            }
+        }
+        let (axis, angle) = Pose::new([0.0; 3], [0.0, 0.0, 0.0, -1.0]).axis_angle();
+        assert_eq!((axis, angle), ([1.0, 0.0, 0.0], 0.0));
+    }
+
+    #[test]
+    fn test_to_openxr_frame_flips_z() {
+        let h = core::f64::consts::FRAC_1_SQRT_2;
+        // 90° about Y turns device forward (+Z) into +X; in OpenXR forward is -Z.
+        let device = Pose::new([1.0, 2.0, 3.0], [0.0, h, 0.0, h]);
+        let openxr = device.to_frame(CoordinateFrame::OpenXr);
+        assert_eq!(openxr.translation, [1.0, 2.0, -3.0]);
+        let [qx, qy, qz, qw] = openxr.quaternion;
+        let expected = Pose::new([0.0; 3], [qx, qy, qz, qw]).rotation;
+        for (row, want) in openxr.rotation.iter().zip(expected) {
+            for (a, b) in row.iter().zip(want) {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+        // Forward (0,0,-1) maps to +X.
+        assert!((-openxr.rotation[0][2] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_firmware_version() {
+        let xr50 = "1V1.04P31||xr50|V1.09|20221207_01|develop|56a1f2a.";
+        assert_eq!(
+            FirmwareVersion::parse(xr50),
+            Some(FirmwareVersion::new(1, 4, 31))
+        );
+        assert_eq!(
+            FirmwareVersion::parse("2.1"),
+            Some(FirmwareVersion::new(2, 1, 0))
+        );
+        assert_eq!(FirmwareVersion::parse("unknown"), None);
+        assert!(FirmwareVersion::parse(xr50).unwrap() < FirmwareVersion::new(2, 1, 0));
+    }
+
+    #[test]
+    fn test_average_handles_sign_flip() {
+        // ±10° yaw around identity, the second expressed with the opposite sign.
+        let (s, c) = (5f64.to_radians().sin(), 5f64.to_radians().cos());
+        let avg = Pose::average(&[
+            pose([0.0, 0.0, 0.0], [0.0, s, 0.0, c]),
+            pose([2.0, 4.0, 6.0], [0.0, s, 0.0, -c]),
+        ])
+        .unwrap();
+
+        assert_eq!(avg.translation, [1.0, 2.0, 3.0]);
+        assert!((avg.quaternion[3].abs() - 1.0).abs() < 1e-9);
+        assert!(avg.euler_deg.iter().all(|a| a.abs() < 1e-6));
+        assert!(Pose::average(&[]).is_none());
+    }
 }
@@ -0,0 +1,159 @@
+//! Discovery of the XR50's UVC video interfaces.
+//!
+//! The XR50 exposes its stereo cameras as standard USB Video Class interfaces
+//! next to the HID SLAM interface. This module only reads the descriptors so the
+//! feed can be handed to a separate UVC capture library; it doesn't stream video.
+//! Whether the cameras actually deliver frames depends on the configure command's
+//! `uvc_mode`, see [`SlamMode::Custom`](crate::SlamMode::Custom).
+
+use crate::protocol::{PID, VID};
+use crate::Result;
+
+const CLASS_VIDEO: u8 = 0x0E;
+const SUBCLASS_VIDEO_CONTROL: u8 = 0x01;
+const SUBCLASS_VIDEO_STREAMING: u8 = 0x02;
+const CS_INTERFACE: u8 = 0x24;
+const VS_FRAME_UNCOMPRESSED: u8 = 0x05;
+const VS_FRAME_MJPEG: u8 = 0x07;
+
+/// UVC interfaces found on the device.
+#[derive(Debug, Clone)]
+pub struct UvcInfo {
+    pub bus_number: u8,
+    pub device_address: u8,
+    /// VideoControl interface numbers.
+    pub control_interfaces: Vec<u8>,
+    pub streaming: Vec<UvcStreamingInterface>,
+}
+
+/// A VideoStreaming interface and the frame sizes it advertises.
+#[derive(Debug, Clone)]
+pub struct UvcStreamingInterface {
+    pub interface: u8,
+    /// Video data endpoint address, from the first alternate setting that has one.
+    pub endpoint: Option<u8>,
+    pub frames: Vec<UvcFrame>,
+}
+
+/// One advertised frame size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UvcFrame {
+    pub width: u16,
+    pub height: u16,
+    /// Default frame rate in Hz.
+    pub fps: f64,
+    pub mjpeg: bool,
+}
+
+/// Read the UVC descriptors of the first connected XR50.
+/// Returns `None` if the device has no video interfaces.
+pub fn query() -> Result<Option<UvcInfo>> {
+    let device = rusb::devices()?
+        .iter()
+        .find(|d| {
+            d.device_descriptor()
+                .map(|desc| desc.vendor_id() == VID && desc.product_id() == PID)
+                .unwrap_or(false)
+        })
+        .ok_or(crate::XvisioError::DeviceNotFound)?;
+    query_device(&device)
+}
+
+/// Read the UVC descriptors of `device`.
+/// Returns `None` if the device has no video interfaces.
+pub fn query_device<T: rusb::UsbContext>(device: &rusb::Device<T>) -> Result<Option<UvcInfo>> {
+    let config = device.active_config_descriptor()?;
+
+    let mut info = UvcInfo {
+        bus_number: device.bus_number(),
+        device_address: device.address(),
+        control_interfaces: Vec::new(),
+        streaming: Vec::new(),
+    };
+    for interface in config.interfaces() {
+        let mut streaming: Option<UvcStreamingInterface> = None;
+        for alt in interface.descriptors() {
+            if alt.class_code() != CLASS_VIDEO {
+                continue;
+            }
+            match alt.sub_class_code() {
+                SUBCLASS_VIDEO_CONTROL if alt.setting_number() == 0 => {
+                    info.control_interfaces.push(alt.interface_number());
+                }
+                SUBCLASS_VIDEO_STREAMING => {
+                    let s = streaming.get_or_insert_with(|| UvcStreamingInterface {
+                        interface: alt.interface_number(),
+                        endpoint: None,
+                        frames: Vec::new(),
+                    });
+                    s.frames.extend(parse_frames(alt.extra()));
+                    if s.endpoint.is_none() {
+                        s.endpoint = alt.endpoint_descriptors().next().map(|ep| ep.address());
+                    }
+                }
+                _ => {}
+            }
+        }
+        info.streaming.extend(streaming);
+    }
+
+    if info.control_interfaces.is_empty() && info.streaming.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(info))
+}
+
+/// Extract frame descriptors from the class-specific bytes of a VideoStreaming interface.
+fn parse_frames(mut extra: &[u8]) -> Vec<UvcFrame> {
+    let mut frames = Vec::new();
+    while extra.len() >= 3 {
+        let len = extra[0] as usize;
+        if len < 3 || len > extra.len() {
+            break;
+        }
+        let desc = &extra[..len];
+        let is_frame = matches!(desc[2], VS_FRAME_UNCOMPRESSED | VS_FRAME_MJPEG);
+        if desc[1] == CS_INTERFACE && is_frame && len >= 25 {
+            let u16_at = |i: usize| u16::from_le_bytes([desc[i], desc[i + 1]]);
+            // Default interval in 100 ns units.
+            let interval = u32::from_le_bytes([desc[21], desc[22], desc[23], desc[24]]);
+            frames.push(UvcFrame {
+                width: u16_at(5),
+                height: u16_at(7),
+                fps: if interval > 0 {
+                    1e7 / interval as f64
+                } else {
+                    0.0
+                },
+                mjpeg: desc[2] == VS_FRAME_MJPEG,
+            });
+        }
+        extra = &extra[len..];
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frames() {
+        // VS_FORMAT_UNCOMPRESSED header (skipped), then a 640x400 @ 30 Hz frame.
+        let mut extra = vec![27, CS_INTERFACE, 0x04];
+        extra.extend([0u8; 24]);
+        let mut frame = vec![30, CS_INTERFACE, VS_FRAME_UNCOMPRESSED, 1, 0];
+        frame.extend(640u16.to_le_bytes());
+        frame.extend(400u16.to_le_bytes());
+        frame.extend([0u8; 12]);
+        frame.extend(333_333u32.to_le_bytes());
+        frame.extend([0u8; 5]);
+        extra.extend(frame);
+
+        let frames = parse_frames(&extra);
+        assert_eq!(frames.len(), 1);
+        assert_eq!((frames[0].width, frames[0].height), (640, 400));
+        assert!((frames[0].fps - 30.0).abs() < 0.01);
+        assert!(!frames[0].mjpeg);
+    }
+}